@@ -1,19 +1,53 @@
-mod board;
+pub(crate) mod betza;
+pub(crate) mod board;
+mod board_file;
+pub(crate) mod board_library;
 mod board_space;
+mod file_format;
 mod game_piece;
+pub(crate) mod game_status;
+pub(crate) mod move_generator;
 mod piece;
 pub(crate) mod piece_catalog;
+pub(crate) mod piece_generator;
 mod piece_move;
+pub(crate) mod piece_value;
+pub(crate) mod player;
+pub(crate) mod promotion;
+pub(crate) mod shuffle;
+#[cfg(test)]
+mod test_support;
 pub(crate) mod texture_registry;
+pub(crate) mod variant_generator;
 
+use crate::{gfx::text, sdl_error::ToSdl};
 use parking_lot::RwLock;
-use sdl2::render::{TextureCreator, WindowCanvas};
-use std::{fmt::Display, fs, rc::Rc};
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{TextureCreator, WindowCanvas},
+    ttf::Font,
+};
+use std::{fmt::Display, rc::Rc};
+
+/// Where the board library looks for board files
+pub const BOARD_DIR: &str = "./chess_boards/";
+/// The board played when none is picked
+pub const DEFAULT_BOARD: &str = "Classic Chess";
 
 pub struct ChessGame<'tc, C> {
     pub piece_catalog: piece_catalog::PieceCatalog,
     pub board: board::Board,
+    pub board_library: board_library::BoardLibrary,
+    /// How boards get shuffled as they load, if they do
+    pub shuffle: Option<shuffle::ShuffleConfig>,
     pub textures: texture_registry::TextureRegistry<'tc, C>,
+    selected_moves: Vec<move_generator::BoardMove>,
+    /// The status after the last finished move, kept `Ongoing` while a promotion is pending
+    status: game_status::GameStatus,
+    mouse_position: (i32, i32),
+    /// Whether the board picker covers the board
+    board_menu_open: bool,
 }
 
 impl<'tc, C> ChessGame<'tc, C> {
@@ -21,28 +55,120 @@ impl<'tc, C> ChessGame<'tc, C> {
         Ok(ChessGame {
             piece_catalog: piece_catalog::PieceCatalog::new()?,
             board: board::Board::new()?,
+            board_library: board_library::BoardLibrary::default(),
+            shuffle: None,
             textures: texture_registry::TextureRegistry::new(texture_creator),
+            selected_moves: vec![],
+            status: game_status::GameStatus::Ongoing,
+            mouse_position: (0, 0),
+            board_menu_open: false,
         })
     }
 
-    pub fn load(&mut self) -> Result<(), crate::Error> {
+    /// Loads the pieces, their images and the board library, then sets up the
+    /// named board, or the default one
+    pub fn load(&mut self, board: Option<&str>) -> Result<(), crate::Error> {
         self.piece_catalog.generate("./chess_pieces/".to_string())?;
         self.textures
             .generate_piece_images("./chess_images".to_string())?;
-        let mut file = fs::read_dir("./chess_boards/")?;
-        // FIXME let user pick a board
-        let board_file = file
-            .find_map(|f| {
-                if let Ok(de) = f {
-                    if de.file_name() == "classic_chess.txt" {
-                        return Some(de);
-                    }
-                }
-                None
-            })
-            .unwrap();
+        self.board_library = board_library::BoardLibrary::scan(BOARD_DIR)?;
+        self.load_board(board.unwrap_or(DEFAULT_BOARD))
+    }
+
+    /// Replaces the game with a fresh one on a board from the library, or on
+    /// any board file by its path
+    pub fn load_board(&mut self, name_or_path: &str) -> Result<(), crate::Error> {
+        let info = self.board_library.find(name_or_path)?;
+        let mut board = board::Board::new()?;
+        board.generate(&info.path, &self.piece_catalog, self.shuffle.as_ref())?;
+        self.board = board;
+        self.selected_moves.clear();
+        self.update_status()
+    }
+
+    /// Replaces the game with a fresh one on a randomly generated board
+    pub fn load_random_variant(&mut self, seed: u64) -> Result<(), crate::Error> {
+        let generator = variant_generator::VariantGenerator::new(
+            &self.piece_catalog,
+            variant_generator::VariantConfig::default(),
+        );
+        self.board = generator.generate(seed)?;
+        self.selected_moves.clear();
+        self.update_status()
+    }
+
+    pub fn board_menu_open(&self) -> bool {
+        self.board_menu_open
+    }
+
+    /// Opens or closes the board picker
+    pub fn toggle_board_menu(&mut self) {
+        self.board_menu_open = !self.board_menu_open;
+    }
+
+    /// Where the board picker draws the `index`th board, as rows over the board
+    pub fn board_menu_area(&self, index: usize) -> Rect {
+        let area = self.textures.area;
+        let count = self.board_library.boards.len().max(1) as u32;
+        let row_height = (area.height() / count).min(area.height() / 4).max(1);
+        let top = area.y() + (area.height() - row_height * count) as i32 / 2;
+        Rect::new(
+            area.x() + area.width() as i32 / 8,
+            top + (row_height * index as u32) as i32,
+            area.width() * 3 / 4,
+            row_height * 7 / 8,
+        )
+    }
+
+    /// The board under the mouse while the board picker is open
+    pub fn hovered_board(&self) -> Option<&board_library::BoardInfo> {
+        if !self.board_menu_open {
+            return None;
+        }
+        self.board_library
+            .boards
+            .iter()
+            .enumerate()
+            .find(|(i, _)| self.board_menu_area(*i).contains_point(self.mouse_position))
+            .map(|(_, info)| info)
+    }
+
+    /// Loads the board under the mouse and closes the picker, returning whether
+    /// the board changed
+    pub fn pick_hovered_board(&mut self) -> Result<bool, crate::Error> {
+        let path = match self.hovered_board() {
+            Some(info) => info.path.to_string_lossy().to_string(),
+            None => return Ok(false),
+        };
+        self.load_board(&path)?;
+        self.board_menu_open = false;
+        Ok(true)
+    }
 
-        self.board.generate(board_file, &self.piece_catalog)?;
+    /// Draws the board picker over the board, one named row per board
+    pub fn render_board_menu(
+        &self,
+        canvas: Rc<RwLock<WindowCanvas>>,
+        font: &Font,
+    ) -> Result<(), crate::Error> {
+        if !self.board_menu_open {
+            return Ok(());
+        }
+        let hovered = self.hovered_board().map(|info| info.path.clone());
+        let mut canvas = canvas.write();
+        canvas.set_draw_color(Color::RGBA(0x00, 0x00, 0x00, 0x80));
+        canvas.fill_rect(self.textures.area).sdl_error()?;
+        for (i, info) in self.board_library.boards.iter().enumerate() {
+            let shade = if Some(&info.path) == hovered.as_ref() {
+                0xc0
+            } else {
+                0x80
+            };
+            let area = self.board_menu_area(i);
+            canvas.set_draw_color(Color::RGB(shade, shade, shade));
+            canvas.fill_rect(area).sdl_error()?;
+            text::draw_text(&mut canvas, font, &info.to_string(), area, Color::BLACK)?;
+        }
         Ok(())
     }
 
@@ -57,12 +183,97 @@ impl<'tc, C> ChessGame<'tc, C> {
         Ok(())
     }
 
+    /// The status of the game for the side to move
+    pub fn game_status(&self) -> Result<game_status::GameStatus, crate::Error> {
+        match self.board.current_player() {
+            Some(player) => move_generator::MoveGenerator::new(&self.board, &self.piece_catalog)
+                .game_status(&player.name),
+            None => Ok(game_status::GameStatus::Ongoing),
+        }
+    }
+
+    /// The board name, followed by the status once the game is no longer simply ongoing
+    pub fn title(&self) -> String {
+        match self.status {
+            game_status::GameStatus::Ongoing => self.board.name.clone(),
+            ref status => format!("{} - {}", self.board.name, status),
+        }
+    }
+
+    /// Works out the status again, unless a promotion still has to finish the move
+    fn update_status(&mut self) -> Result<(), crate::Error> {
+        self.status = match self.board.pending_promotion {
+            Some(_) => game_status::GameStatus::Ongoing,
+            None => self.game_status()?,
+        };
+        Ok(())
+    }
+
+    pub fn make_move(
+        &mut self,
+        board_move: &move_generator::BoardMove,
+    ) -> Result<(), crate::Error> {
+        self.board.make_move(&self.piece_catalog, board_move)?;
+        self.update_status()
+    }
+
+    /// The headless way to finish a promotion; the GUI picks through `mouse_left_click`
+    pub fn choose_promotion(&mut self, piece_name: &str) -> Result<(), crate::Error> {
+        self.board.choose_promotion(piece_name)?;
+        self.update_status()
+    }
+
     pub fn mouse_hover(&mut self, x: &i32, y: &i32) -> Result<(), crate::Error> {
+        self.mouse_position = (*x, *y);
         self.board.mouse_hover(x, y)
     }
 
     pub fn mouse_left_click(&mut self) -> Result<(), crate::Error> {
-        self.board.mouse_left_click()
+        if let Some(pending) = &self.board.pending_promotion {
+            let count = pending.choices.len();
+            let choice = pending.choices.iter().enumerate().find_map(|(i, choice)| {
+                if self
+                    .board
+                    .promotion_choice_area(i, count)
+                    .contains_point(self.mouse_position)
+                {
+                    Some(choice.clone())
+                } else {
+                    None
+                }
+            });
+            if let Some(choice) = choice {
+                self.choose_promotion(&choice)?;
+            }
+            return Ok(());
+        }
+        let hovered = self.board.hovered_position();
+        let chosen_move = self
+            .selected_moves
+            .iter()
+            .find(|board_move| Some(board_move.to) == hovered)
+            .cloned();
+        if let Some(board_move) = chosen_move {
+            self.make_move(&board_move)?;
+            self.selected_moves.clear();
+        } else {
+            let current_team = self
+                .board
+                .current_player()
+                .map(|player| player.name.clone());
+            self.selected_moves = match hovered
+                .and_then(|(horz, vert)| self.board.get_piece(horz, vert))
+                .filter(|game_piece| Some(&game_piece.team_name) == current_team.as_ref())
+            {
+                Some(game_piece) => {
+                    move_generator::MoveGenerator::new(&self.board, &self.piece_catalog)
+                        .legal_moves(game_piece)?
+                }
+                None => vec![],
+            };
+        }
+        self.board.mark_moves(&self.selected_moves);
+        Ok(())
     }
 }
 
@@ -0,0 +1,606 @@
+use std::fmt::Display;
+
+use super::piece::Piece;
+use super::piece_move::{MoveRules, PieceMove, DEFAULT_PAWN_FIRST_DISTANCE};
+
+/// The leaper atoms, each with the shorter and longer side of its jump
+const ATOMS: [(char, (i32, i32)); 9] = [
+    ('W', (0, 1)),
+    ('F', (1, 1)),
+    ('D', (0, 2)),
+    ('N', (1, 2)),
+    ('A', (2, 2)),
+    ('H', (0, 3)),
+    ('C', (1, 3)),
+    ('Z', (2, 3)),
+    ('G', (3, 3)),
+];
+
+const WAZIR: (i32, i32) = (0, 1);
+const FERZ: (i32, i32) = (1, 1);
+
+/// How the moves of one Betza component behave once they reach a square
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Both,
+    Leap,
+    Kill,
+    Run(Option<u32>),
+    Slide(Option<u32>),
+    Hop,
+    CannonKill,
+}
+
+/// Reads Betza notation such as `WfcF` or `N` into piece moves
+///
+/// Riders are written doubled (`WW`) or with a range (`W3`, where `W0` has no limit).
+/// `m` and `c` make leapers move or capture only, `m` makes a rider slide without
+/// capturing, `g` makes a rider hop like a grasshopper, `cp` a cannon capture, and
+/// `ifmW2` is a pawn's first move. `O` castles.
+pub fn piece_moves(notation: &str) -> Result<Vec<PieceMove>, BetzaError> {
+    let unreadable = |reason: String| BetzaError::Unreadable {
+        notation: notation.to_string(),
+        reason,
+    };
+    let chars = notation.chars().collect::<Vec<_>>();
+    let mut moves = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let mut modifiers = vec![];
+        while i < chars.len() && chars[i].is_ascii_lowercase() {
+            modifiers.push(chars[i]);
+            i += 1;
+        }
+        let atom = *chars
+            .get(i)
+            .ok_or_else(|| unreadable(String::from("modifiers with no atom after them")))?;
+        i += 1;
+        let (atoms, mut rider) = match atom {
+            'K' => (vec![WAZIR, FERZ], false),
+            'Q' => (vec![WAZIR, FERZ], true),
+            'R' => (vec![WAZIR], true),
+            'B' => (vec![FERZ], true),
+            'O' => (vec![], false),
+            _ => (
+                vec![atom_offset(atom)
+                    .ok_or_else(|| unreadable(format!("there is no atom '{}'", atom)))?],
+                false,
+            ),
+        };
+        if !rider && atom != 'O' && chars.get(i) == Some(&atom) {
+            rider = true;
+            i += 1;
+        }
+        let mut digits = String::new();
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            digits.push(chars[i]);
+            i += 1;
+        }
+        let mut limit = None;
+        if !digits.is_empty() {
+            rider = true;
+            let range = digits
+                .parse::<u32>()
+                .map_err(|_| unreadable(format!("the range {} is too large", digits)))?;
+            limit = Some(range).filter(|&n| n > 0);
+        }
+        if atom == 'O' {
+            push_move(&mut moves, PieceMove::new_special(MoveRules::Castle));
+            continue;
+        }
+
+        let directions = modifiers
+            .iter()
+            .copied()
+            .filter(|m| "fblrvs".contains(*m))
+            .collect::<Vec<_>>();
+        let has = |m: char| modifiers.contains(&m);
+        if let Some(m) = modifiers.iter().find(|m| !"fblrvsmcpgi".contains(**m)) {
+            return Err(unreadable(format!("there is no modifier '{}'", m)));
+        }
+        if has('i') {
+            // Only a pawn's first move is known, and it reaches as far as the range says
+            if !has('m') || has('c') || limit.is_none_or(|n| n < 2) || atoms != [WAZIR] {
+                return Err(unreadable(String::from(
+                    "i only makes a first move as ifmW with a range of 2 or more",
+                )));
+            }
+            let mut pawn_first = PieceMove::new_special(MoveRules::PawnFirst);
+            pawn_first.max_distance = limit;
+            push_move(&mut moves, pawn_first);
+            continue;
+        }
+        // Hops and cannon captures go as far as the screen, with no range to limit them
+        if (has('g') || has('p')) && limit.is_some() {
+            return Err(unreadable(format!(
+                "hoppers take no range, found {}{}",
+                atom, digits
+            )));
+        }
+        let modes = match (rider, has('m'), has('c'), has('p'), has('g')) {
+            (false, m, c, false, false) if m == c => vec![Mode::Leap, Mode::Kill],
+            (false, true, false, false, false) => vec![Mode::Leap],
+            (false, false, true, false, false) => vec![Mode::Kill],
+            (true, false, false, false, false) => vec![Mode::Run(limit)],
+            (true, true, false, false, false) => vec![Mode::Slide(limit)],
+            (true, false, false, false, true) => vec![Mode::Hop],
+            (true, false, true, true, false) => vec![Mode::CannonKill],
+            _ => {
+                let used = modifiers
+                    .iter()
+                    .filter(|m| "mcpg".contains(**m))
+                    .collect::<String>();
+                return Err(unreadable(format!(
+                    "the modifiers '{}' don't go together on {}",
+                    used, atom
+                )));
+            }
+        };
+
+        for &offset in &atoms {
+            let groups = direction_groups(&directions, offset);
+            let vectors = atom_vectors(offset)
+                .into_iter()
+                .filter(|&v| groups.is_empty() || groups.iter().any(|g| group_matches(g, v)));
+            for (x, y) in vectors {
+                for &mode in &modes {
+                    let (rules, max_distance) = match mode {
+                        Mode::Leap => (MoveRules::Leap, None),
+                        Mode::Kill => (MoveRules::Kill, None),
+                        Mode::Run(limit) => (MoveRules::Run, limit),
+                        Mode::Slide(limit) => (MoveRules::Slide, limit),
+                        Mode::Hop => (MoveRules::Hop, None),
+                        Mode::CannonKill => (MoveRules::CannonKill, None),
+                        Mode::Both => unreachable!(),
+                    };
+                    let mut piece_move = PieceMove::new(y, -x, rules);
+                    piece_move.max_distance = max_distance;
+                    push_move(&mut moves, piece_move);
+                }
+            }
+        }
+    }
+    Ok(moves)
+}
+
+/// Writes a piece's moves as compact Betza notation
+pub fn from_piece(piece: &Piece) -> Result<String, BetzaError> {
+    let mut groups = Vec::<((usize, Mode), Vec<(i32, i32)>)>::new();
+    let mut specials = String::new();
+    for piece_move in &piece.move_set {
+        let inexpressible = || {
+            BetzaError::Inexpressible(format!(
+                "{:?} {} {}",
+                piece_move.rules, piece_move.left, piece_move.forward
+            ))
+        };
+        let mode = match piece_move.rules {
+            MoveRules::Leap => Mode::Leap,
+            MoveRules::Kill => Mode::Kill,
+            MoveRules::Run => Mode::Run(piece_move.max_distance),
+            MoveRules::Slide => Mode::Slide(piece_move.max_distance),
+            MoveRules::Hop => Mode::Hop,
+            MoveRules::CannonKill => Mode::CannonKill,
+            MoveRules::PawnFirst => {
+                let reach = piece_move
+                    .max_distance
+                    .unwrap_or(DEFAULT_PAWN_FIRST_DISTANCE);
+                specials.push_str(&format!("ifmW{}", reach));
+                continue;
+            }
+            MoveRules::Castle => {
+                specials.push('O');
+                continue;
+            }
+        };
+        if !piece_move.legs.is_empty() || piece_move.min_distance != 1 {
+            return Err(inexpressible());
+        }
+        let vector = (-piece_move.left, piece_move.forward);
+        let atom = ATOMS
+            .iter()
+            .position(|&(_, offset)| atom_vectors(offset).contains(&vector))
+            .ok_or_else(inexpressible)?;
+        match groups.iter_mut().find(|(key, _)| *key == (atom, mode)) {
+            Some((_, vectors)) => vectors.push(vector),
+            None => groups.push(((atom, mode), vec![vector])),
+        }
+    }
+
+    // A leap and a kill in the same direction are one plain Betza move
+    let mut components = vec![];
+    for ((atom, mode), vectors) in &groups {
+        let vectors_of = |mode: Mode| {
+            groups
+                .iter()
+                .find(|(key, _)| *key == (*atom, mode))
+                .map_or(vec![], |(_, vectors)| vectors.clone())
+        };
+        match mode {
+            Mode::Leap => {
+                let kills = vectors_of(Mode::Kill);
+                let (both, leaps): (Vec<_>, Vec<_>) =
+                    vectors.iter().partition(|v| kills.contains(v));
+                components.push(((*atom, Mode::Both), both));
+                components.push(((*atom, Mode::Leap), leaps));
+            }
+            Mode::Kill => {
+                let leaps = vectors_of(Mode::Leap);
+                let kills = vectors.iter().filter(|v| !leaps.contains(v)).copied();
+                components.push(((*atom, Mode::Kill), kills.collect()));
+            }
+            _ => components.push(((*atom, *mode), vectors.clone())),
+        }
+    }
+    components.retain(|(_, vectors)| !vectors.is_empty());
+    components.sort_by_key(|&((atom, mode), _)| (mode_order(mode), atom));
+
+    let mut notation = String::new();
+    let is_full = |atom: usize, mode: Mode| {
+        components.iter().any(|((a, m), vectors)| {
+            (*a, *m) == (atom, mode) && vectors.len() == atom_vectors(ATOMS[atom].1).len()
+        })
+    };
+    let (wazir, ferz) = (0, 1);
+    let mut written = vec![];
+    if is_full(wazir, Mode::Both) && is_full(ferz, Mode::Both) {
+        notation.push('K');
+        written.extend(&[(wazir, Mode::Both), (ferz, Mode::Both)]);
+    }
+    for &(atom, mode) in &components.iter().map(|(key, _)| *key).collect::<Vec<_>>() {
+        let (prefix, limit) = match mode {
+            Mode::Run(limit) => ("", limit),
+            Mode::Slide(limit) => ("m", limit),
+            _ => continue,
+        };
+        let range = limit.map_or(String::new(), |n| n.to_string());
+        if atom == wazir && is_full(wazir, mode) && is_full(ferz, mode) {
+            notation.push_str(&format!("{}Q{}", prefix, range));
+            written.extend(&[(wazir, mode), (ferz, mode)]);
+        } else if atom == wazir && is_full(wazir, mode) {
+            notation.push_str(&format!("{}R{}", prefix, range));
+            written.push((wazir, mode));
+        } else if atom == ferz && is_full(ferz, mode) && !written.contains(&(ferz, mode)) {
+            notation.push_str(&format!("{}B{}", prefix, range));
+            written.push((ferz, mode));
+        }
+    }
+
+    for ((atom, mode), vectors) in &components {
+        if written.contains(&(*atom, *mode)) {
+            continue;
+        }
+        let (letter, offset) = ATOMS[*atom];
+        let modifiers = match mode {
+            Mode::Leap | Mode::Slide(_) => "m",
+            Mode::Kill => "c",
+            Mode::Hop => "g",
+            Mode::CannonKill => "cp",
+            Mode::Both | Mode::Run(_) => "",
+        };
+        let suffix = match mode {
+            Mode::Run(Some(n)) | Mode::Slide(Some(n)) => n.to_string(),
+            Mode::Run(None) | Mode::Slide(None) | Mode::Hop | Mode::CannonKill => {
+                letter.to_string()
+            }
+            _ => String::new(),
+        };
+        let direction_groups = cover(vectors, offset).ok_or_else(|| {
+            BetzaError::Inexpressible(format!("{:?} {} on {:?}", mode, letter, vectors))
+        })?;
+        // Orthogonal atoms never pair their direction letters, so those can share a component
+        if offset.0 == 0 {
+            notation.push_str(&format!(
+                "{}{}{}{}",
+                direction_groups.concat(),
+                modifiers,
+                letter,
+                suffix
+            ));
+        } else {
+            for group in direction_groups {
+                notation.push_str(&format!("{}{}{}{}", group, modifiers, letter, suffix));
+            }
+        }
+    }
+    notation.push_str(&specials);
+    Ok(notation)
+}
+
+fn mode_order(mode: Mode) -> u32 {
+    match mode {
+        Mode::Both => 0,
+        Mode::Run(_) => 1,
+        Mode::Slide(_) => 2,
+        Mode::Leap => 3,
+        Mode::Kill => 4,
+        Mode::Hop => 5,
+        Mode::CannonKill => 6,
+    }
+}
+
+fn atom_offset(letter: char) -> Option<(i32, i32)> {
+    ATOMS
+        .iter()
+        .find(|&&(atom, _)| atom == letter)
+        .map(|&(_, offset)| offset)
+}
+
+/// Every direction an atom jumps in, as (right, forward)
+fn atom_vectors((short, long): (i32, i32)) -> Vec<(i32, i32)> {
+    let mut vectors = vec![];
+    for &(x, y) in &[(short, long), (long, short)] {
+        for &(sx, sy) in &[(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+            let vector = (x * sx, y * sy);
+            if !vectors.contains(&vector) {
+                vectors.push(vector);
+            }
+        }
+    }
+    vectors
+}
+
+fn direction_matches(letter: char, (x, y): (i32, i32)) -> bool {
+    match letter {
+        'f' => y > 0,
+        'b' => y < 0,
+        'l' => x < 0,
+        'r' => x > 0,
+        'v' => y != 0 && y.abs() >= x.abs(),
+        's' => x != 0 && x.abs() >= y.abs(),
+        _ => false,
+    }
+}
+
+/// Whether a direction group selects the vector; a group is one letter, or two
+/// letters that must both hold, where a doubled letter (`ff`) means the narrow
+/// moves that way
+fn group_matches(group: &[char], vector: (i32, i32)) -> bool {
+    match *group {
+        [letter] => direction_matches(letter, vector),
+        [first, second] => {
+            let second = match (first == second, first) {
+                (true, 'f') | (true, 'b') => 'v',
+                (true, _) => 's',
+                (false, _) => second,
+            };
+            direction_matches(first, vector) && direction_matches(second, vector)
+        }
+        _ => false,
+    }
+}
+
+/// Splits direction letters into groups; only atoms with diagonal or oblique
+/// jumps pair letters such as `fr`
+fn direction_groups(directions: &[char], offset: (i32, i32)) -> Vec<Vec<char>> {
+    let mut groups = vec![];
+    let mut i = 0;
+    while i < directions.len() {
+        let pairs = offset.0 != 0
+            && "fblr".contains(directions[i])
+            && i + 1 < directions.len()
+            && !matches!(
+                (directions[i], directions[i + 1]),
+                ('f', 'b') | ('b', 'f') | ('l', 'r') | ('r', 'l')
+            );
+        if pairs {
+            groups.push(directions[i..i + 2].to_vec());
+            i += 2;
+        } else {
+            groups.push(vec![directions[i]]);
+            i += 1;
+        }
+    }
+    groups
+}
+
+/// The fewest direction groups that select exactly the vectors, if any do
+fn cover(vectors: &[(i32, i32)], offset: (i32, i32)) -> Option<Vec<String>> {
+    let all = atom_vectors(offset);
+    if all.iter().all(|v| vectors.contains(v)) {
+        return Some(vec![String::new()]);
+    }
+    let mut candidates = vec![];
+    for first in "fblrvs".chars() {
+        candidates.push(vec![first]);
+    }
+    if offset.0 != 0 {
+        for first in "fblr".chars() {
+            for second in "fblrvs".chars() {
+                candidates.push(vec![first, second]);
+            }
+        }
+    }
+    let mut candidates = candidates
+        .into_iter()
+        .filter(|group| direction_groups(group, offset).len() == 1)
+        .map(|group| {
+            let selected = all
+                .iter()
+                .copied()
+                .filter(|&v| group_matches(&group, v))
+                .collect::<Vec<_>>();
+            (group, selected)
+        })
+        .filter(|(_, selected)| {
+            !selected.is_empty() && selected.iter().all(|v| vectors.contains(v))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|(_, selected)| std::cmp::Reverse(selected.len()));
+
+    let mut covered = vec![];
+    let mut groups = vec![];
+    for (group, selected) in candidates {
+        if selected.iter().any(|v| !covered.contains(v)) {
+            covered.extend(selected);
+            groups.push(group.into_iter().collect::<String>());
+        }
+    }
+    if vectors.iter().all(|v| covered.contains(v)) {
+        Some(groups)
+    } else {
+        None
+    }
+}
+
+fn push_move(moves: &mut Vec<PieceMove>, piece_move: PieceMove) {
+    if !moves.contains(&piece_move) {
+        moves.push(piece_move);
+    }
+}
+
+#[derive(Debug)]
+pub enum BetzaError {
+    Unreadable { notation: String, reason: String },
+    Inexpressible(String),
+}
+
+impl std::error::Error for BetzaError {}
+
+impl Display for BetzaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unreadable { notation, reason } => {
+                write!(f, "Unreadable Betza notation '{}': {}", notation, reason)
+            }
+            Self::Inexpressible(piece_move) => {
+                write!(f, "No Betza notation for the move '{}'", piece_move)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_game::test_support::catalog;
+    use expect_test::{expect, Expect};
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    fn moves(notation: &str) -> String {
+        piece_moves(notation)
+            .unwrap()
+            .iter()
+            .map(|m| match m.max_distance {
+                Some(max) => format!("{:?} {} {} max {}", m.rules, m.left, m.forward, max),
+                None => format!("{:?} {} {}", m.rules, m.left, m.forward),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn read_pawn_moves() {
+        check(
+            expect![[r#"
+            Leap 0 1
+            Kill -1 1
+            Kill 1 1
+            PawnFirst 0 0 max 2"#]],
+            moves("fmWfcFifmW2"),
+        );
+    }
+
+    #[test]
+    fn read_directions() {
+        check(
+            expect![[r#"
+            Leap -1 2
+            Kill -1 2
+            Leap 1 2
+            Kill 1 2"#]],
+            moves("ffN"),
+        );
+        check(
+            expect![[r#"
+            Leap -1 1
+            Kill -1 1"#]],
+            moves("frF"),
+        );
+        check(
+            expect![[r#"
+            Run -1 0 max 3
+            Run 1 0 max 3"#]],
+            moves("sW3"),
+        );
+    }
+
+    #[test]
+    fn reports_unreadable_notation() {
+        let error = |notation: &str| piece_moves(notation).unwrap_err().to_string();
+        check(
+            expect![[r#"Unreadable Betza notation 'Y': there is no atom 'Y'"#]],
+            error("Y"),
+        );
+        check(
+            expect![[
+                r#"Unreadable Betza notation 'mcR': the modifiers 'mc' don't go together on R"#
+            ]],
+            error("mcR"),
+        );
+        check(
+            expect![[r#"Unreadable Betza notation 'fm': modifiers with no atom after them"#]],
+            error("fm"),
+        );
+        check(
+            expect![[
+                r#"Unreadable Betza notation 'ifmW1': i only makes a first move as ifmW with a range of 2 or more"#
+            ]],
+            error("ifmW1"),
+        );
+        check(
+            expect![[r#"Unreadable Betza notation 'gR3': hoppers take no range, found R3"#]],
+            error("gR3"),
+        );
+        check(
+            expect![[r#"Unreadable Betza notation 'mRcpR2': hoppers take no range, found R2"#]],
+            error("mRcpR2"),
+        );
+    }
+
+    #[test]
+    fn write_chess_pieces() {
+        let catalog = catalog();
+        let mut names = catalog.catalog.keys().collect::<Vec<_>>();
+        names.sort();
+        check(
+            expect![[r#"
+                Bishop: B
+                King: KO
+                Knight: N
+                Pawn: fmWfcFifmW2
+                Queen: Q
+                Rook: R"#]],
+            names
+                .iter()
+                .map(|name| {
+                    format!(
+                        "{}: {}",
+                        name,
+                        from_piece(catalog.get_piece(name).unwrap()).unwrap()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let notations = [
+            "fmWfcF", "ffNbsN", "gQ", "cpRmW", "R4B", "frFblF", "KDD", "mRcpR", "fmQ3",
+        ];
+        for notation in &notations {
+            let mut piece = Piece::new();
+            piece.move_set = piece_moves(notation).unwrap();
+            let written = from_piece(&piece).unwrap();
+            let mut read = piece_moves(&written).unwrap();
+            read.sort_by_key(|m| format!("{:?}", m));
+            piece.move_set.sort_by_key(|m| format!("{:?}", m));
+            assert_eq!(read, piece.move_set, "{} became {}", notation, written);
+        }
+    }
+}
@@ -1,18 +1,46 @@
 use sdl2::{pixels::Color, rect::Rect};
-use std::{fs::DirEntry, fs::File, io::BufRead, io::BufReader};
+use std::{fmt::Display, fs::File, io::BufRead, io::BufReader, path::Path};
 
+use super::board_file::{self, BoardStatement};
+use super::board_space::BoardSpace;
 use super::game_piece::GamePiece;
+use super::game_status::GameStatus;
+use super::move_generator::{BoardMove, MoveGenerator, MoveKind};
 use super::piece_catalog::PieceCatalog;
+use super::player::{Orientation, Player};
+use super::promotion::{PendingPromotion, PromotionArea, PromotionError, PromotionZone};
+use super::shuffle::{self, ShuffleConfig};
 use super::InvalidFormatError;
-use super::{board_space::BoardSpace, game_piece};
 
+/// The most squares a board may have along either side
+pub const MAX_BOARD_SIZE: u32 = 64;
+
+/// The squares a multi-step first move passed over, which can be captured
+/// "in passing" on the following move
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnPassant {
+    pub team_name: String,
+    pub squares: Vec<(u32, u32)>,
+    pub piece_position: (u32, u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Board {
     pub name: String,
     pub grid: Vec<BoardSpace>,
     pub width: u32,
     pub height: u32,
-    pub players: Vec<String>,
+    pub players: Vec<Player>,
     pub dead_pieces: Vec<GamePiece>, // Maybe save the collection of MOVES and just remove dead pieces
+    /// Index into `players` of the side to move
+    pub current_player: usize,
+    pub en_passant: Option<EnPassant>,
+    pub promotion_zones: Vec<PromotionZone>,
+    /// Whether runs carry on past disabled squares instead of stopping at them
+    pub runs_jump_holes: bool,
+    /// Whether a side left with only royal pieces loses, instead of playing on
+    pub bare_royal_loses: bool,
+    pub pending_promotion: Option<PendingPromotion>,
     pub space_size: u32,
     pub horz_offset: i32,
     pub vert_offset: i32,
@@ -25,6 +53,12 @@ impl Board {
             grid: vec![],
             players: vec![],
             dead_pieces: vec![],
+            current_player: 0,
+            en_passant: None,
+            promotion_zones: vec![],
+            runs_jump_holes: false,
+            bare_royal_loses: false,
+            pending_promotion: None,
             width: 0,
             height: 0,
             space_size: 0,
@@ -33,87 +67,426 @@ impl Board {
         })
     }
 
+    /// Reads a board file, then shuffles its starting pieces if asked to
     pub fn generate(
         &mut self,
-        file: DirEntry,
+        path: &Path,
+        chess_pieces: &PieceCatalog,
+        shuffle: Option<&ShuffleConfig>,
+    ) -> Result<(), crate::Error> {
+        let reader = BufReader::new(File::open(path)?);
+        self.read_board(reader, chess_pieces)?;
+        if let Some(config) = shuffle {
+            shuffle::shuffle_board(self, chess_pieces, config)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_board<R: BufRead>(
+        &mut self,
+        reader: R,
         chess_pieces: &PieceCatalog,
     ) -> Result<(), crate::Error> {
-        if file.file_type()?.is_file() && file.file_name().to_string_lossy().ends_with(".txt") {
-            let reader = BufReader::new(File::open(file.path())?);
-            let mut line_num = 1;
-            for line in reader.lines() {
-                let line = line?;
-                if line.starts_with('-') {
-                    continue;
-                } else if line.starts_with("Name") {
-                    // Name: Classic Chess
-                    self.name = line;
-                } else if line.starts_with("Size") {
-                    // Size: 8 8
-                    let mut line_iter = line.split_whitespace().skip(1);
-                    self.width = line_iter
-                        .next()
-                        .ok_or_else(|| InvalidFormatError::new(line_num, line.clone()))?
-                        .parse()?;
-                    self.height = line_iter
-                        .next()
-                        .ok_or_else(|| InvalidFormatError::new(line_num, line.clone()))?
-                        .parse()?;
-                    for i in 0..self.width {
-                        for j in 0..self.height {
-                            let color = if i % 2 == j % 2 {
-                                Color::BLACK
-                            } else {
-                                Color::WHITE
-                            };
-                            self.grid.push(BoardSpace::new(i, j, color)?);
-                        }
+        for (line_num, statement) in board_file::read_board(reader)? {
+            let invalid =
+                |token: String| -> crate::Error { InvalidFormatError::new(line_num, token).into() };
+            match statement {
+                BoardStatement::Name { name } => self.name = name,
+                BoardStatement::Size { width, height } => {
+                    // A second size would wipe the squares already set up
+                    if width == 0
+                        || height == 0
+                        || width > MAX_BOARD_SIZE
+                        || height > MAX_BOARD_SIZE
+                        || !self.grid.is_empty()
+                    {
+                        return Err(invalid(format!("{} {}", width, height)));
+                    }
+                    // Generate blank board_space to self.grid
+                    self.resize(width, height)?;
+                }
+                BoardStatement::Player { name, orientation } => {
+                    if self.has_player(&name) {
+                        return Err(invalid(name));
+                    }
+                    self.players.push(Player::new(name, orientation));
+                }
+                BoardStatement::Disabled { position } => {
+                    let index = self.find_board_space_index(position.0, position.1);
+                    if index < 0 {
+                        return Err(invalid(format!("{} {}", position.0, position.1)));
+                    }
+                    self.grid[index as usize].is_active = false;
+                }
+                BoardStatement::Holes { jump } => self.runs_jump_holes = jump,
+                BoardStatement::BareRoyal { loses } => self.bare_royal_loses = loses,
+                BoardStatement::PromotionZone { team_name, ranks } => {
+                    if !self.has_player(&team_name) {
+                        return Err(invalid(team_name));
+                    }
+                    self.promotion_zones.push(PromotionZone {
+                        team_name,
+                        area: PromotionArea::Ranks(ranks),
+                    });
+                }
+                BoardStatement::PromotionSquare {
+                    team_name,
+                    position,
+                } => {
+                    if !self.has_player(&team_name) {
+                        return Err(invalid(team_name));
+                    }
+                    if self.get_space(position.0, position.1).is_none() {
+                        return Err(invalid(format!("{} {}", position.0, position.1)));
                     }
-                // Generate blank board_space to self.grid
-                } else if line.starts_with("Player") {
-                    // Players: white
-                    self.players.push(line);
-                } else if line.starts_with("Disabled") {
-                    // Update board_space at location in grid and disable
-                } else if line.starts_with("Piece") {
-                    let mut line_iter = line.split_whitespace().skip(1);
-                    let horz_pos = line_iter
-                        .next()
-                        .ok_or_else(|| InvalidFormatError::new(line_num, line.clone()))?
-                        .parse()?;
-                    let vert_pos = line_iter
-                        .next()
-                        .ok_or_else(|| InvalidFormatError::new(line_num, line.clone()))?
-                        .parse()?;
-                    let team_name = line_iter
-                        .next()
-                        .ok_or_else(|| InvalidFormatError::new(line_num, line.clone()))?
-                        .to_string();
-                    let piece = chess_pieces.get_piece(
-                        &line_iter
-                            .next()
-                            .ok_or_else(|| InvalidFormatError::new(line_num, line.clone()))?
-                            .to_string(),
-                    )?;
-                    let piece_index = self.find_board_space_index(horz_pos, vert_pos);
-                    if piece_index >= 0 {
-                        let game_pieces =
-                            &mut self.grid.get_mut(piece_index as usize).unwrap().game_pieces;
-                        game_pieces.push(GamePiece::new(
-                            piece.name.clone(),
-                            team_name,
-                            horz_pos,
-                            vert_pos,
-                        )?);
+                    self.promotion_zones.push(PromotionZone {
+                        team_name,
+                        area: PromotionArea::Square(position.0, position.1),
+                    });
+                }
+                BoardStatement::Piece {
+                    position,
+                    team_name,
+                    piece_name,
+                } => {
+                    if !self.has_player(&team_name) {
+                        return Err(invalid(team_name));
+                    }
+                    if !self.is_active(position) || self.get_piece(position.0, position.1).is_some()
+                    {
+                        return Err(invalid(format!("{} {}", position.0, position.1)));
                     }
+                    let piece = chess_pieces.get_piece(&piece_name)?;
+                    self.place_piece(GamePiece::new(
+                        piece.name.clone(),
+                        team_name,
+                        position.0,
+                        position.1,
+                    )?);
                 }
-                line_num += 1;
             }
         }
         Ok(())
     }
 
+    /// The board file statements that set this board up as it stands now
+    pub fn statements(&self) -> Vec<BoardStatement> {
+        let mut statements = vec![];
+        if !self.name.is_empty() {
+            statements.push(BoardStatement::Name {
+                name: self.name.clone(),
+            });
+        }
+        statements.push(BoardStatement::Size {
+            width: self.width,
+            height: self.height,
+        });
+        for player in &self.players {
+            statements.push(BoardStatement::Player {
+                name: player.name.clone(),
+                orientation: player.orientation,
+            });
+        }
+        for zone in &self.promotion_zones {
+            let team_name = zone.team_name.clone();
+            statements.push(match zone.area {
+                PromotionArea::Ranks(ranks) => BoardStatement::PromotionZone { team_name, ranks },
+                PromotionArea::Square(horz, vert) => BoardStatement::PromotionSquare {
+                    team_name,
+                    position: (horz, vert),
+                },
+            });
+        }
+        if self.runs_jump_holes {
+            statements.push(BoardStatement::Holes { jump: true });
+        }
+        if self.bare_royal_loses {
+            statements.push(BoardStatement::BareRoyal { loses: true });
+        }
+        for space in self.grid.iter().filter(|space| !space.is_active) {
+            statements.push(BoardStatement::Disabled {
+                position: (space.horz_position + 1, space.vert_position + 1),
+            });
+        }
+        for game_piece in self.collect_game_pieces() {
+            statements.push(BoardStatement::Piece {
+                position: (game_piece.horz_position, game_piece.vert_position),
+                team_name: game_piece.team_name.clone(),
+                piece_name: game_piece.piece_name.clone(),
+            });
+        }
+        statements
+    }
+
+    /// Writes the board in the board file format, so that reading it back gives
+    /// the same board
+    pub fn write_board(&self) -> String {
+        board_file::write_board(&self.statements())
+    }
+
+    fn has_player(&self, team_name: &str) -> bool {
+        self.players.iter().any(|player| player.name == team_name)
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), crate::Error> {
+        self.width = width;
+        self.height = height;
+        self.grid.clear();
+        for i in 0..self.width {
+            for j in 0..self.height {
+                let color = if i % 2 == j % 2 {
+                    Color::BLACK
+                } else {
+                    Color::WHITE
+                };
+                self.grid.push(BoardSpace::new(i, j, color)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts a piece on its square, unless the square is missing or disabled
+    pub fn place_piece(&mut self, game_piece: GamePiece) {
+        let piece_index =
+            self.find_board_space_index(game_piece.horz_position, game_piece.vert_position);
+        if piece_index >= 0 && self.grid[piece_index as usize].is_active {
+            self.grid[piece_index as usize].game_pieces.push(game_piece);
+        }
+    }
+
+    pub fn orientation(&self, team_name: &str) -> Orientation {
+        self.players
+            .iter()
+            .find(|player| player.name == team_name)
+            .map(|player| player.orientation)
+            .unwrap_or_default()
+    }
+
+    pub fn get_space(&self, horz_pos: u32, vert_pos: u32) -> Option<&BoardSpace> {
+        let index = self.find_board_space_index(horz_pos, vert_pos);
+        if index >= 0 {
+            self.grid.get(index as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_active(&self, (horz_pos, vert_pos): (u32, u32)) -> bool {
+        self.get_space(horz_pos, vert_pos)
+            .map(|space| space.is_active)
+            .unwrap_or(false)
+    }
+
+    pub fn get_piece(&self, horz_pos: u32, vert_pos: u32) -> Option<&GamePiece> {
+        self.get_space(horz_pos, vert_pos)
+            .and_then(|space| space.game_pieces.first())
+    }
+
+    pub fn take_piece(&mut self, horz_pos: u32, vert_pos: u32) -> Option<GamePiece> {
+        let index = self.find_board_space_index(horz_pos, vert_pos);
+        if index < 0 || self.grid[index as usize].game_pieces.is_empty() {
+            return None;
+        }
+        Some(self.grid[index as usize].game_pieces.remove(0))
+    }
+
+    /// Moves whatever stands on `from` to `to`, returning the piece it displaced
+    pub fn move_piece(&mut self, from: (u32, u32), to: (u32, u32)) -> Option<GamePiece> {
+        if from == to || !self.is_active(to) {
+            return None;
+        }
+        let game_piece = self.take_piece(from.0, from.1)?;
+        let captured = self.take_piece(to.0, to.1);
+        self.place_moved_piece(game_piece, to);
+        captured
+    }
+
+    fn place_moved_piece(&mut self, mut game_piece: GamePiece, (horz_pos, vert_pos): (u32, u32)) {
+        game_piece.horz_position = horz_pos;
+        game_piece.vert_position = vert_pos;
+        game_piece.has_moved = true;
+        self.place_piece(game_piece);
+    }
+
+    /// Carries out a generated move, returning any captured piece
+    pub fn apply_move(&mut self, board_move: &BoardMove) -> Option<GamePiece> {
+        self.en_passant = None;
+        match board_move.kind {
+            MoveKind::Move | MoveKind::Capture => self.move_piece(board_move.from, board_move.to),
+            MoveKind::PawnFirst { step } => {
+                let captured = self.move_piece(board_move.from, board_move.to);
+                let mut squares = vec![];
+                let mut current = board_move.from;
+                while let Some(next) = self.offset_position(current, step) {
+                    if next == board_move.to {
+                        break;
+                    }
+                    squares.push(next);
+                    current = next;
+                }
+                self.en_passant =
+                    self.get_piece(board_move.to.0, board_move.to.1)
+                        .map(|game_piece| EnPassant {
+                            team_name: game_piece.team_name.clone(),
+                            squares,
+                            piece_position: board_move.to,
+                        });
+                captured
+            }
+            MoveKind::EnPassant { captured } => {
+                self.move_piece(board_move.from, board_move.to);
+                self.take_piece(captured.0, captured.1)
+            }
+            MoveKind::Castle {
+                partner_from,
+                partner_to,
+            } => {
+                // Both pieces leave first, since in shuffled setups either may
+                // land on the other's starting square
+                let royal = self.take_piece(board_move.from.0, board_move.from.1);
+                let partner = self.take_piece(partner_from.0, partner_from.1);
+                if let Some(royal) = royal {
+                    self.place_moved_piece(royal, board_move.to);
+                }
+                if let Some(partner) = partner {
+                    self.place_moved_piece(partner, partner_to);
+                }
+                None
+            }
+        }
+    }
+
+    /// Plays a legal move for the side to move, like `apply_move`, then passes
+    /// the turn on. A piece ending in its promotion zone holds the turn until
+    /// `choose_promotion` is called.
+    pub fn make_move(
+        &mut self,
+        catalog: &PieceCatalog,
+        board_move: &BoardMove,
+    ) -> Result<(), crate::Error> {
+        if self.pending_promotion.is_some() {
+            return Err(PromotionError::Pending.into());
+        }
+        let game_piece = self
+            .get_piece(board_move.from.0, board_move.from.1)
+            .ok_or(IllegalMoveError::NoPiece(board_move.from))?;
+        if let Some(player) = self.current_player() {
+            let status = MoveGenerator::new(self, catalog).game_status(&player.name)?;
+            if status.is_over() {
+                return Err(IllegalMoveError::GameOver(status).into());
+            }
+            if game_piece.team_name != player.name {
+                return Err(IllegalMoveError::NotYourTurn(game_piece.team_name.clone()).into());
+            }
+        }
+        if !MoveGenerator::new(self, catalog)
+            .legal_moves(game_piece)?
+            .contains(board_move)
+        {
+            return Err(IllegalMoveError::NotLegal(board_move.from, board_move.to).into());
+        }
+
+        if let Some(captured) = self.apply_move(board_move) {
+            self.dead_pieces.push(captured);
+        }
+        if let Some(game_piece) = self.get_piece(board_move.to.0, board_move.to.1) {
+            let piece = catalog.get_piece(&game_piece.piece_name)?;
+            if !piece.promotions.is_empty()
+                && self.in_promotion_zone(&game_piece.team_name, board_move.to)
+            {
+                self.pending_promotion = Some(PendingPromotion {
+                    position: board_move.to,
+                    team_name: game_piece.team_name.clone(),
+                    choices: piece.promotions.clone(),
+                });
+                return Ok(());
+            }
+        }
+        self.advance_turn();
+        Ok(())
+    }
+
+    pub fn current_player(&self) -> Option<&Player> {
+        self.players.get(self.current_player)
+    }
+
+    /// Hands the turn to the next player in declaration order, skipping
+    /// players who have no pieces left
+    fn advance_turn(&mut self) {
+        for _ in 0..self.players.len() {
+            self.current_player = (self.current_player + 1) % self.players.len();
+            let name = &self.players[self.current_player].name;
+            if self
+                .collect_game_pieces()
+                .iter()
+                .any(|game_piece| &game_piece.team_name == name)
+            {
+                break;
+            }
+        }
+    }
+
+    pub fn choose_promotion(&mut self, piece_name: &str) -> Result<(), crate::Error> {
+        let pending = self
+            .pending_promotion
+            .as_ref()
+            .ok_or(PromotionError::NotPending)?;
+        if !pending.choices.iter().any(|choice| choice == piece_name) {
+            return Err(PromotionError::InvalidChoice(piece_name.to_string()).into());
+        }
+        let index = self.find_board_space_index(pending.position.0, pending.position.1);
+        if index >= 0 {
+            if let Some(game_piece) = self.grid[index as usize].game_pieces.first_mut() {
+                game_piece.piece_name = piece_name.to_string();
+            }
+        }
+        self.pending_promotion = None;
+        self.advance_turn();
+        Ok(())
+    }
+
+    pub fn in_promotion_zone(&self, team_name: &str, position: (u32, u32)) -> bool {
+        let orientation = self.orientation(team_name);
+        self.promotion_zones.iter().any(|zone| {
+            zone.team_name == team_name
+                && zone.contains(position, orientation, (self.width, self.height))
+        })
+    }
+
+    /// Where the `index`th of `count` promotion choices is drawn, in a row
+    /// across the middle of the board
+    pub fn promotion_choice_area(&self, index: usize, count: usize) -> Rect {
+        let row_width = count as i32 * self.space_size as i32;
+        let board_width = (self.width * self.space_size) as i32;
+        let board_height = (self.height * self.space_size) as i32;
+        Rect::new(
+            self.horz_offset
+                + (board_width - row_width) / 2
+                + index as i32 * self.space_size as i32,
+            self.vert_offset + (board_height - self.space_size as i32) / 2,
+            self.space_size,
+            self.space_size,
+        )
+    }
+
+    /// Steps from a position by a board-relative offset, staying on the board
+    pub fn offset_position(
+        &self,
+        (horz_pos, vert_pos): (u32, u32),
+        (horz_offset, vert_offset): (i32, i32),
+    ) -> Option<(u32, u32)> {
+        let horz = horz_pos as i64 + horz_offset as i64;
+        let vert = vert_pos as i64 + vert_offset as i64;
+        if horz < 1 || vert < 1 || horz > self.width as i64 || vert > self.height as i64 {
+            return None;
+        }
+        Some((horz as u32, vert as u32))
+    }
+
     pub fn find_board_space_index(&self, horz_pos: u32, vert_pos: u32) -> i32 {
+        if horz_pos == 0 || vert_pos == 0 {
+            return -1;
+        }
         self.grid
             .iter()
             .position(|sp| sp.horz_position == horz_pos - 1 && sp.vert_position == vert_pos - 1)
@@ -151,27 +524,265 @@ impl Board {
                 self.space_size,
                 self.space_size,
             );
-            if rect.contains_point((*x, *y)) {
-                grid_space.hovered = true;
-            } else {
-                grid_space.hovered = false;
-            }
+            grid_space.hovered = grid_space.is_active && rect.contains_point((*x, *y));
         }
         Ok(())
     }
 
-    pub fn mouse_left_click(&mut self) -> Result<(), crate::Error> {
-        for grid_space in self.grid.iter() {
-            if grid_space.hovered {
-                println!(
-                    "{}",
-                    "Clicked: ".to_owned()
-                        + &grid_space.horz_position.to_string()
-                        + ", "
-                        + &grid_space.vert_position.to_string()
-                );
+    pub fn hovered_position(&self) -> Option<(u32, u32)> {
+        self.grid
+            .iter()
+            .find(|space| space.hovered)
+            .map(|space| (space.horz_position + 1, space.vert_position + 1))
+    }
+
+    pub fn mark_moves(&mut self, moves: &[BoardMove]) {
+        for space in self.grid.iter_mut() {
+            space.reset_status();
+        }
+        for board_move in moves {
+            let index = self.find_board_space_index(board_move.to.0, board_move.to.1);
+            if index < 0 {
+                continue;
+            }
+            let space = &mut self.grid[index as usize];
+            match board_move.kind {
+                MoveKind::Move | MoveKind::PawnFirst { .. } | MoveKind::Castle { .. } => {
+                    space.available_to_move = true
+                }
+                MoveKind::Capture | MoveKind::EnPassant { .. } => space.available_to_kill = true,
             }
         }
-        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum IllegalMoveError {
+    NoPiece((u32, u32)),
+    NotYourTurn(String),
+    NotLegal((u32, u32), (u32, u32)),
+    GameOver(GameStatus),
+}
+
+impl Display for IllegalMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IllegalMoveError::NoPiece((horz, vert)) => {
+                write!(f, "No piece to move at {}, {}", horz, vert)
+            }
+            IllegalMoveError::NotYourTurn(team) => write!(f, "It is not {}'s turn", team),
+            IllegalMoveError::NotLegal(from, to) => write!(
+                f,
+                "Illegal move from {}, {} to {}, {}",
+                from.0, from.1, to.0, to.1
+            ),
+            IllegalMoveError::GameOver(status) => write!(f, "The game is over: {}", status),
+        }
+    }
+}
+
+impl std::error::Error for IllegalMoveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_game::test_support::{board, catalog};
+
+    fn step(from: (u32, u32), to: (u32, u32), kind: MoveKind) -> BoardMove {
+        BoardMove { from, to, kind }
+    }
+
+    #[test]
+    fn turns_alternate_and_captures_are_kept() {
+        let catalog = catalog();
+        let mut board = board(&[
+            ("King", "white", 5, 1),
+            ("Rook", "white", 1, 1),
+            ("King", "black", 5, 8),
+            ("Knight", "black", 1, 6),
+        ]);
+        assert!(board
+            .make_move(&catalog, &step((5, 8), (5, 7), MoveKind::Move))
+            .is_err());
+        assert!(board
+            .make_move(&catalog, &step((1, 1), (2, 2), MoveKind::Move))
+            .is_err());
+        board
+            .make_move(&catalog, &step((1, 1), (1, 6), MoveKind::Capture))
+            .unwrap();
+        assert_eq!(board.dead_pieces[0].piece_name, "Knight");
+        assert_eq!(board.current_player().unwrap().name, "black");
+        board
+            .make_move(&catalog, &step((5, 8), (5, 7), MoveKind::Move))
+            .unwrap();
+        assert_eq!(board.current_player().unwrap().name, "white");
+    }
+
+    #[test]
+    fn turns_rotate_through_every_player() {
+        let catalog = catalog();
+        let mut board = board(&[
+            ("Rook", "white", 1, 1),
+            ("Rook", "black", 8, 8),
+            ("Rook", "red", 1, 8),
+        ]);
+        board
+            .players
+            .push(Player::new("red".to_string(), Orientation::Right));
+        let moves = [
+            step((1, 1), (2, 1), MoveKind::Move),
+            step((8, 8), (8, 7), MoveKind::Move),
+            step((1, 8), (2, 8), MoveKind::Move),
+        ];
+        for board_move in &moves {
+            board.make_move(&catalog, board_move).unwrap();
+        }
+        assert_eq!(board.current_player().unwrap().name, "white");
+    }
+
+    #[test]
+    fn cross_board_disables_corners() {
+        let catalog = catalog();
+        let mut board = Board::new().unwrap();
+        board
+            .generate(
+                Path::new("./chess_boards/four_player_chess.txt"),
+                &catalog,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            board.grid.iter().filter(|space| !space.is_active).count(),
+            36
+        );
+
+        board.take_piece(5, 1);
+        board.take_piece(6, 1);
+        board.take_piece(7, 1);
+        let king = board.get_piece(8, 1).unwrap();
+        let castles: Vec<_> = MoveGenerator::new(&board, &catalog)
+            .legal_moves(king)
+            .unwrap()
+            .into_iter()
+            .filter(|board_move| matches!(board_move.kind, MoveKind::Castle { .. }))
+            .map(|board_move| board_move.to)
+            .collect();
+        assert_eq!(castles, vec![(6, 1)]);
+    }
+
+    #[test]
+    fn rejects_out_of_range_and_unknown_players() {
+        let catalog = catalog();
+        let read = |data: &str| match Board::new().unwrap().read_board(data.as_bytes(), &catalog) {
+            Ok(()) => String::from("ok"),
+            Err(e) => e.to_string(),
+        };
+        assert_eq!(
+            read("Size: 8 8\nPlayer: white\n\nPiece: 9 1 white King"),
+            "Invalid file format (line 4): '9 1'"
+        );
+        assert_eq!(
+            read("Size: 8 8\nPlayer: white\nPiece: 1 1 whte King"),
+            "Invalid file format (line 3): 'whte'"
+        );
+        assert_eq!(
+            read("Size: 8 8\nDisabled: 3 0"),
+            "Invalid file format (line 2): '3 0'"
+        );
+        assert_eq!(
+            read("Size: 100000 100000"),
+            "Invalid file format (line 1): '100000 100000'"
+        );
+        assert_eq!(
+            read("Size: 8 8\nDisabled: 3 3\nSize: 6 6"),
+            "Invalid file format (line 3): '6 6'"
+        );
+        assert_eq!(
+            read("Size: 8 8\nPlayer: white\nPiece: 1 1 white King\nPiece: 1 1 white Rook"),
+            "Invalid file format (line 4): '1 1'"
+        );
+        assert_eq!(
+            read("Size: 8 8\nPlayer: white\nPiece: 1 1 white King"),
+            "ok"
+        );
+    }
+
+    #[test]
+    fn algebraic_files_and_any_case_names() {
+        let catalog = catalog();
+        let mut board = Board::new().unwrap();
+        board
+            .read_board(
+                "Size: 8 8\nPlayer: player1 up\nPiece: a 1 player1 rook\nPiece: d 1 player1 QUEEN"
+                    .as_bytes(),
+                &catalog,
+            )
+            .unwrap();
+        assert_eq!(board.get_piece(1, 1).unwrap().piece_name, "Rook");
+        assert_eq!(board.get_piece(4, 1).unwrap().piece_name, "Queen");
+    }
+
+    #[test]
+    fn bare_royals_lose_only_where_the_board_says() {
+        let catalog = catalog();
+        let mut board = Board::new().unwrap();
+        board
+            .generate(
+                Path::new("./chess_boards/classic_chess.txt"),
+                &catalog,
+                None,
+            )
+            .unwrap();
+        for horz in 1..=8 {
+            for vert in 1..=8 {
+                if ![(1, 1), (5, 1), (5, 8)].contains(&(horz, vert)) {
+                    board.take_piece(horz, vert);
+                }
+            }
+        }
+        board.current_player = 1;
+        let status = |board: &Board| {
+            MoveGenerator::new(board, &catalog)
+                .game_status("black")
+                .unwrap()
+        };
+        assert_eq!(status(&board), GameStatus::Ongoing);
+
+        let mut bare = board.clone();
+        bare.bare_royal_loses = true;
+        assert_eq!(
+            status(&bare),
+            GameStatus::BareRoyal {
+                loser: "black".to_string()
+            }
+        );
+        let king_steps = step((5, 8), (5, 7), MoveKind::Move);
+        assert_eq!(
+            bare.make_move(&catalog, &king_steps)
+                .unwrap_err()
+                .to_string(),
+            "The game is over: black has only royal pieces left and loses"
+        );
+        board.make_move(&catalog, &king_steps).unwrap();
+    }
+
+    #[test]
+    fn shipped_boards_read_back_the_same() {
+        let catalog = catalog();
+        let mut files = std::fs::read_dir("./chess_boards/")
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".txt"))
+            .collect::<Vec<_>>();
+        files.sort_by_key(|entry| entry.file_name());
+        assert!(!files.is_empty());
+        for file in files {
+            let mut board = Board::new().unwrap();
+            board.generate(&file.path(), &catalog, None).unwrap();
+            let written = board.write_board();
+            let mut read_back = Board::new().unwrap();
+            read_back.read_board(written.as_bytes(), &catalog).unwrap();
+            assert_eq!(read_back, board, "{}", written);
+        }
     }
 }
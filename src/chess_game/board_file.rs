@@ -0,0 +1,684 @@
+use std::{io::BufRead, iter::Peekable, mem::discriminant};
+
+use super::file_format::{file_word, SEPARATOR};
+use super::player::Orientation;
+use super::InvalidFormatError;
+
+#[derive(Debug, PartialEq)]
+enum BoardTokenKind {
+    NameKeyword,
+    SizeKeyword,
+    PlayerKeyword,
+    DisabledKeyword,
+    HolesKeyword,
+    BareRoyalKeyword,
+    PromotionZoneKeyword,
+    PromotionSquareKeyword,
+    PieceKeyword,
+
+    Colon,
+    Number(i32),
+    Text(String),
+
+    EndOfFile,
+}
+
+#[derive(Debug)]
+struct BoardToken {
+    line: usize,
+    text: String,
+    kind: BoardTokenKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardStatement {
+    Name {
+        name: String,
+    },
+    Size {
+        width: u32,
+        height: u32,
+    },
+    Player {
+        name: String,
+        orientation: Orientation,
+    },
+    Disabled {
+        position: (u32, u32),
+    },
+    Holes {
+        jump: bool,
+    },
+    BareRoyal {
+        loses: bool,
+    },
+    PromotionZone {
+        team_name: String,
+        ranks: u32,
+    },
+    PromotionSquare {
+        team_name: String,
+        position: (u32, u32),
+    },
+    Piece {
+        position: (u32, u32),
+        team_name: String,
+        piece_name: String,
+    },
+}
+
+/// Reads the digits of a number token, which may be too big for a number
+fn number_text(line: usize, text: &str) -> Result<i32, crate::Error> {
+    text.parse()
+        .map_err(|_| InvalidFormatError::new(line, text.to_string()).into())
+}
+
+/// Reads a board file into its statements, each paired with the line it starts on
+pub fn read_board<R: BufRead>(reader: R) -> Result<Vec<(usize, BoardStatement)>, crate::Error> {
+    let tokens = lex_board(reader)?;
+    parse_board(tokens.into_iter())
+}
+
+/// Writes statements in the board file format, one per line, with a separator
+/// wherever the kind of statement changes
+pub fn write_board(statements: &[BoardStatement]) -> String {
+    let mut out = String::from(SEPARATOR);
+    let mut previous = None;
+    for statement in statements {
+        // The name and size share the first group, as in the shipped boards
+        let group = match statement {
+            BoardStatement::Name { .. } => discriminant(&BoardStatement::Size {
+                width: 0,
+                height: 0,
+            }),
+            _ => discriminant(statement),
+        };
+        if previous.is_some_and(|previous| previous != group) {
+            out.push('\n');
+            out.push_str(SEPARATOR);
+        }
+        previous = Some(group);
+        out.push('\n');
+        out.push_str(&write_statement(statement));
+    }
+    out.push('\n');
+    out.push_str(SEPARATOR);
+    out.push('\n');
+    out
+}
+
+fn write_statement(statement: &BoardStatement) -> String {
+    match statement {
+        // A name reads back up to the end of its line, so it can't span lines
+        BoardStatement::Name { name } => format!("Name: {}", name.replace('\n', " ").trim()),
+        BoardStatement::Size { width, height } => format!("Size: {} {}", width, height),
+        BoardStatement::Player { name, orientation } => {
+            format!("Player: {} {}", file_word(name), orientation.name())
+        }
+        BoardStatement::Disabled { position } => {
+            format!("Disabled: {} {}", position.0, position.1)
+        }
+        BoardStatement::Holes { jump } => {
+            format!("Holes: {}", if *jump { "jump" } else { "stop" })
+        }
+        BoardStatement::BareRoyal { loses } => {
+            format!("BareRoyal: {}", if *loses { "loses" } else { "plays" })
+        }
+        BoardStatement::PromotionZone { team_name, ranks } => {
+            format!("PromotionZone: {} {}", file_word(team_name), ranks)
+        }
+        BoardStatement::PromotionSquare {
+            team_name,
+            position,
+        } => format!(
+            "PromotionSquare: {} {} {}",
+            file_word(team_name),
+            position.0,
+            position.1
+        ),
+        BoardStatement::Piece {
+            position,
+            team_name,
+            piece_name,
+        } => format!(
+            "Piece: {} {} {} {}",
+            position.0,
+            position.1,
+            file_word(team_name),
+            file_word(piece_name)
+        ),
+    }
+}
+
+fn lex_board<R: BufRead>(reader: R) -> Result<Vec<BoardToken>, crate::Error> {
+    let mut reader = reader.bytes().peekable();
+    let mut line = 1;
+    let mut tokens = Vec::<BoardToken>::new();
+
+    while let Some(b) = reader.next() {
+        let b = b?;
+
+        match b {
+            b'\n' => line += 1,
+            b'\t' | b' ' | b'\r' => {}
+            b':' => {
+                let names = tokens.last().is_some_and(|token| {
+                    token.kind == BoardTokenKind::NameKeyword && token.line == line
+                });
+                tokens.push(BoardToken {
+                    line,
+                    text: String::from(":"),
+                    kind: BoardTokenKind::Colon,
+                });
+                // A board name is the rest of the line as written, so any
+                // character may be in it
+                if names {
+                    let mut name = vec![];
+                    while let Some(Ok(b)) = reader.peek() {
+                        if *b == b'\n' {
+                            break;
+                        }
+                        name.push(*b);
+                        reader.next();
+                    }
+                    let name = String::from_utf8_lossy(&name).trim().to_string();
+                    if !name.is_empty() {
+                        tokens.push(BoardToken {
+                            line,
+                            kind: BoardTokenKind::Text(name.clone()),
+                            text: name,
+                        });
+                    }
+                }
+            }
+            b'-' => {
+                if let Some(Ok(b'0'..=b'9')) = reader.peek() {
+                    let mut text = String::from("-");
+                    while let Some(&Ok(b @ b'0'..=b'9')) = reader.peek() {
+                        text.push(b as char);
+                        reader.next();
+                    }
+                    tokens.push(BoardToken {
+                        line,
+                        kind: BoardTokenKind::Number(number_text(line, &text)?),
+                        text,
+                    });
+                } else {
+                    // Dashes start separators and commented-out lines
+                    while let Some(Ok(b)) = reader.peek() {
+                        if *b == b'\n' {
+                            break;
+                        }
+                        reader.next();
+                    }
+                }
+            }
+            b if b.is_ascii_digit() => {
+                let mut text = (b as char).to_string();
+                while let Some(&Ok(b @ b'0'..=b'9')) = reader.peek() {
+                    text.push(b as char);
+                    reader.next();
+                }
+                tokens.push(BoardToken {
+                    line,
+                    kind: BoardTokenKind::Number(number_text(line, &text)?),
+                    text,
+                });
+            }
+            b if b.is_ascii_alphabetic() => {
+                let mut word = (b as char).to_string();
+                while let Some(&Ok(mut b)) = reader.peek() {
+                    if b.is_ascii_alphanumeric() || b == b'.' || b == b'_' || b == b'-' {
+                        if b == b'_' {
+                            b = b' ';
+                        }
+                        word.push(b as char);
+                        reader.next();
+                    } else {
+                        break;
+                    }
+                }
+                let kind = match word.as_str() {
+                    "Name" => BoardTokenKind::NameKeyword,
+                    "Size" => BoardTokenKind::SizeKeyword,
+                    "Player" => BoardTokenKind::PlayerKeyword,
+                    "Disabled" => BoardTokenKind::DisabledKeyword,
+                    "Holes" => BoardTokenKind::HolesKeyword,
+                    "BareRoyal" => BoardTokenKind::BareRoyalKeyword,
+                    "PromotionZone" => BoardTokenKind::PromotionZoneKeyword,
+                    "PromotionSquare" => BoardTokenKind::PromotionSquareKeyword,
+                    "Piece" => BoardTokenKind::PieceKeyword,
+                    _ => BoardTokenKind::Text(word.clone()),
+                };
+                tokens.push(BoardToken {
+                    line,
+                    kind,
+                    text: word,
+                })
+            }
+            _ => return Err(InvalidFormatError::new(line, (b as char).to_string()).into()),
+        }
+    }
+    tokens.push(BoardToken {
+        kind: BoardTokenKind::EndOfFile,
+        line,
+        text: String::new(),
+    });
+    Ok(tokens)
+}
+
+fn colon(tokens: &mut Peekable<impl Iterator<Item = BoardToken>>) -> Result<(), crate::Error> {
+    let colon = tokens.next().unwrap();
+    if colon.kind != BoardTokenKind::Colon {
+        return Err(InvalidFormatError::new(colon.line, colon.text).into());
+    }
+    Ok(())
+}
+
+fn number(tokens: &mut Peekable<impl Iterator<Item = BoardToken>>) -> Result<u32, crate::Error> {
+    let number = tokens.next().unwrap();
+    match number.kind {
+        BoardTokenKind::Number(n) if n >= 0 => Ok(n as u32),
+        _ => Err(InvalidFormatError::new(number.line, number.text).into()),
+    }
+}
+
+fn text(tokens: &mut Peekable<impl Iterator<Item = BoardToken>>) -> Result<String, crate::Error> {
+    let text = tokens.next().unwrap();
+    match text.kind {
+        BoardTokenKind::Text(text) => Ok(text),
+        _ => Err(InvalidFormatError::new(text.line, text.text).into()),
+    }
+}
+
+/// Turns algebraic file letters into a 1-based column: a-z, then aa, ab...
+fn file_number(letters: &str) -> Option<u32> {
+    if letters.is_empty() || !letters.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    letters.bytes().try_fold(0u32, |column, b| {
+        column
+            .checked_mul(26)?
+            .checked_add(u32::from(b.to_ascii_lowercase() - b'a') + 1)
+    })
+}
+
+/// A column, given either as a number or as algebraic file letters
+fn file(tokens: &mut Peekable<impl Iterator<Item = BoardToken>>) -> Result<u32, crate::Error> {
+    let file = tokens.next().unwrap();
+    match &file.kind {
+        BoardTokenKind::Number(n) if *n >= 0 => Ok(*n as u32),
+        BoardTokenKind::Text(letters) => {
+            file_number(letters).ok_or_else(|| InvalidFormatError::new(file.line, file.text).into())
+        }
+        _ => Err(InvalidFormatError::new(file.line, file.text).into()),
+    }
+}
+
+fn position(
+    tokens: &mut Peekable<impl Iterator<Item = BoardToken>>,
+) -> Result<(u32, u32), crate::Error> {
+    let horz_pos = file(tokens)?;
+    let vert_pos = number(tokens)?;
+    Ok((horz_pos, vert_pos))
+}
+
+fn board_statement(
+    tokens: &mut Peekable<impl Iterator<Item = BoardToken>>,
+) -> Result<(usize, BoardStatement), crate::Error> {
+    let keyword = tokens.next().unwrap();
+    let line = keyword.line;
+    let statement = match keyword.kind {
+        BoardTokenKind::NameKeyword => {
+            // Name: Classic Chess
+            // The lexer reads the rest of the line as the name, whatever is in it
+            colon(tokens)?;
+            let mut words = vec![];
+            while tokens.peek().unwrap().line == line
+                && tokens.peek().unwrap().kind != BoardTokenKind::EndOfFile
+            {
+                words.push(tokens.next().unwrap().text);
+            }
+            if words.is_empty() {
+                let token = tokens.next().unwrap();
+                return Err(InvalidFormatError::new(token.line, token.text).into());
+            }
+            BoardStatement::Name {
+                name: words.join(" "),
+            }
+        }
+        BoardTokenKind::SizeKeyword => {
+            // Size: 8 8
+            colon(tokens)?;
+            let width = number(tokens)?;
+            let height = number(tokens)?;
+            BoardStatement::Size { width, height }
+        }
+        BoardTokenKind::PlayerKeyword => {
+            // Player: white up
+            colon(tokens)?;
+            let name = text(tokens)?;
+            let next = tokens.peek().unwrap();
+            let orientation = match &next.kind {
+                BoardTokenKind::Text(orientation) if next.line == line => {
+                    let orientation = Orientation::from_name(orientation)
+                        .ok_or_else(|| InvalidFormatError::new(next.line, next.text.clone()))?;
+                    tokens.next();
+                    orientation
+                }
+                _ => Orientation::default(),
+            };
+            BoardStatement::Player { name, orientation }
+        }
+        BoardTokenKind::DisabledKeyword => {
+            // Disabled: 1 7
+            colon(tokens)?;
+            BoardStatement::Disabled {
+                position: position(tokens)?,
+            }
+        }
+        BoardTokenKind::HolesKeyword => {
+            // Holes: stop
+            colon(tokens)?;
+            let setting = tokens.next().unwrap();
+            let jump = match setting.text.as_str() {
+                "stop" => false,
+                "jump" => true,
+                _ => return Err(InvalidFormatError::new(setting.line, setting.text).into()),
+            };
+            BoardStatement::Holes { jump }
+        }
+        BoardTokenKind::BareRoyalKeyword => {
+            // BareRoyal: loses
+            colon(tokens)?;
+            let setting = tokens.next().unwrap();
+            let loses = match setting.text.as_str() {
+                "plays" => false,
+                "loses" => true,
+                _ => return Err(InvalidFormatError::new(setting.line, setting.text).into()),
+            };
+            BoardStatement::BareRoyal { loses }
+        }
+        BoardTokenKind::PromotionZoneKeyword => {
+            // PromotionZone: white 1
+            colon(tokens)?;
+            let team_name = text(tokens)?;
+            let ranks = number(tokens)?;
+            BoardStatement::PromotionZone { team_name, ranks }
+        }
+        BoardTokenKind::PromotionSquareKeyword => {
+            // PromotionSquare: white 4 8
+            colon(tokens)?;
+            let team_name = text(tokens)?;
+            let position = position(tokens)?;
+            BoardStatement::PromotionSquare {
+                team_name,
+                position,
+            }
+        }
+        BoardTokenKind::PieceKeyword => {
+            // Piece: a 1 white Rook
+            colon(tokens)?;
+            let position = position(tokens)?;
+            let team_name = text(tokens)?;
+            let piece_name = text(tokens)?;
+            BoardStatement::Piece {
+                position,
+                team_name,
+                piece_name,
+            }
+        }
+        _ => return Err(InvalidFormatError::new(keyword.line, keyword.text).into()),
+    };
+    Ok((line, statement))
+}
+
+fn parse_board(
+    tokens: impl Iterator<Item = BoardToken>,
+) -> Result<Vec<(usize, BoardStatement)>, crate::Error> {
+    let mut tokens = tokens.peekable();
+    let mut statements = vec![];
+    while tokens.peek().unwrap().kind != BoardTokenKind::EndOfFile {
+        statements.push(board_statement(&mut tokens)?);
+    }
+    Ok(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, Expect};
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    fn error(data: &str) -> String {
+        match read_board(data.as_bytes()) {
+            Ok(statements) => format!("{:?}", statements),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn lex_separators_and_numbers() {
+        let data = "
+            ----------------------------
+            - Disabled: 1 7
+            Size: 14 -2
+            ";
+        let tokens = lex_board(data.as_bytes()).unwrap();
+        check(
+            expect![[r#"
+                [
+                    BoardToken {
+                        line: 4,
+                        text: "Size",
+                        kind: SizeKeyword,
+                    },
+                    BoardToken {
+                        line: 4,
+                        text: ":",
+                        kind: Colon,
+                    },
+                    BoardToken {
+                        line: 4,
+                        text: "14",
+                        kind: Number(
+                            14,
+                        ),
+                    },
+                    BoardToken {
+                        line: 4,
+                        text: "-2",
+                        kind: Number(
+                            -2,
+                        ),
+                    },
+                    BoardToken {
+                        line: 5,
+                        text: "",
+                        kind: EndOfFile,
+                    },
+                ]"#]],
+            format!("{:#?}", tokens),
+        );
+    }
+
+    #[test]
+    fn read_basic_board() {
+        let data = "
+            Name: Four Player Chess
+            Size: 8 8
+            Player: white up
+            Player: black
+            Disabled: 1 7
+            Piece: 5 1 white King
+        ";
+        let statements = read_board(data.as_bytes()).unwrap();
+        check(
+            expect![[r#"
+                [
+                    (
+                        2,
+                        Name {
+                            name: "Four Player Chess",
+                        },
+                    ),
+                    (
+                        3,
+                        Size {
+                            width: 8,
+                            height: 8,
+                        },
+                    ),
+                    (
+                        4,
+                        Player {
+                            name: "white",
+                            orientation: Up,
+                        },
+                    ),
+                    (
+                        5,
+                        Player {
+                            name: "black",
+                            orientation: Up,
+                        },
+                    ),
+                    (
+                        6,
+                        Disabled {
+                            position: (
+                                1,
+                                7,
+                            ),
+                        },
+                    ),
+                    (
+                        7,
+                        Piece {
+                            position: (
+                                5,
+                                1,
+                            ),
+                            team_name: "white",
+                            piece_name: "King",
+                        },
+                    ),
+                ]"#]],
+            format!("{:#?}", statements),
+        );
+    }
+
+    #[test]
+    fn algebraic_files() {
+        check(
+            expect![[
+                r#"[Some(1), Some(4), Some(26), Some(27), Some(28), Some(52), Some(53), None, None]"#
+            ]],
+            format!(
+                "{:?}",
+                ["a", "D", "z", "aa", "ab", "az", "ba", "a1", ""]
+                    .iter()
+                    .map(|letters| file_number(letters))
+                    .collect::<Vec<_>>()
+            ),
+        );
+        check(
+            expect![[
+                r#"[(1, Piece { position: (1, 1), team_name: "player1", piece_name: "rook" }), (2, PromotionSquare { team_name: "player2", position: (4, 1) }), (3, Disabled { position: (27, 14) })]"#
+            ]],
+            error("Piece: a 1 player1 rook\nPromotionSquare: player2 d 1\nDisabled: aa 14"),
+        );
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        check(
+            expect![[r#"Invalid file format (line 2): 'Pieec'"#]],
+            error("Size: 8 8\nPieec: 1 1 white King"),
+        );
+        check(
+            expect![[r#"Invalid file format (line 3): ''"#]],
+            error("Size: 8 8\n\nPiece: 1 1 white"),
+        );
+        check(
+            expect![[r#"Invalid file format (line 1): 'sideways'"#]],
+            error("Player: white sideways"),
+        );
+        check(
+            expect![[r#"Invalid file format (line 1): '-1'"#]],
+            error("Disabled: -1 3"),
+        );
+        check(
+            expect![[r#"Invalid file format (line 1): 'a-b'"#]],
+            error("Piece: a-b 1 white King"),
+        );
+        check(
+            expect![[r#"Invalid file format (line 1): 'a'"#]],
+            error("Size: a 8"),
+        );
+        check(
+            expect![[r#"Invalid file format (line 2): '99999999999'"#]],
+            error("Size: 8 8\nDisabled: 99999999999 1"),
+        );
+        check(
+            expect![[r#"Invalid file format (line 1): '-99999999999'"#]],
+            error("Disabled: -99999999999 1"),
+        );
+    }
+
+    #[test]
+    fn write_statements_back_out() {
+        let data = "
+            Name: Tiny Board (Bob's - 4x5)
+            Size: 4 5
+            Player: white up
+            Player: dark_side down-left
+            PromotionZone: white 1
+            PromotionSquare: dark_side b 1
+            Holes: jump
+            BareRoyal: loses
+            Disabled: 1 5
+            Disabled: 4 5
+            Piece: 1 1 white King
+            Piece: 4 4 dark_side Great_Bishop
+        ";
+        let statements = read_board(data.as_bytes())
+            .unwrap()
+            .into_iter()
+            .map(|(_, statement)| statement)
+            .collect::<Vec<_>>();
+        let written = write_board(&statements);
+        check(
+            expect![[r#"
+            ----------------------------
+            Name: Tiny Board (Bob's - 4x5)
+            Size: 4 5
+            ----------------------------
+            Player: white up
+            Player: dark_side down-left
+            ----------------------------
+            PromotionZone: white 1
+            ----------------------------
+            PromotionSquare: dark_side 2 1
+            ----------------------------
+            Holes: jump
+            ----------------------------
+            BareRoyal: loses
+            ----------------------------
+            Disabled: 1 5
+            Disabled: 4 5
+            ----------------------------
+            Piece: 1 1 white King
+            Piece: 4 4 dark_side Great_Bishop
+            ----------------------------
+        "#]],
+            written.clone(),
+        );
+        let read_back = read_board(written.as_bytes()).unwrap();
+        let read_back = read_back.into_iter().map(|(_, statement)| statement);
+        assert_eq!(read_back.collect::<Vec<_>>(), statements);
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use super::board_file::{self, BoardStatement};
+
+/// What the board picker shows about a board file, read without setting the
+/// board up
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub player_count: usize,
+}
+
+impl BoardInfo {
+    /// Reads the header of a board file; a board with no `Name:` goes by its file name
+    pub fn read(path: &Path) -> Result<BoardInfo, crate::Error> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut info = BoardInfo {
+            name: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_path_buf(),
+            width: 0,
+            height: 0,
+            player_count: 0,
+        };
+        for (_, statement) in board_file::read_board(reader)? {
+            match statement {
+                BoardStatement::Name { name } => info.name = name,
+                BoardStatement::Size { width, height } => {
+                    info.width = width;
+                    info.height = height;
+                }
+                BoardStatement::Player { .. } => info.player_count += 1,
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+}
+
+impl Display for BoardInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}x{}, {} players)",
+            self.name, self.width, self.height, self.player_count
+        )
+    }
+}
+
+/// Every board file in a directory, in file name order
+#[derive(Debug, Default)]
+pub struct BoardLibrary {
+    pub boards: Vec<BoardInfo>,
+    /// The files that couldn't be read as boards, with what went wrong
+    pub problems: Vec<String>,
+}
+
+impl BoardLibrary {
+    /// Reads every `.txt` file in the directory, setting aside the ones that
+    /// aren't boards so the rest still get listed
+    pub fn scan(dir_path: &str) -> Result<BoardLibrary, crate::Error> {
+        let mut paths = vec![];
+        for path in fs::read_dir(dir_path)? {
+            let file = path?;
+            if file.file_type()?.is_file() && file.file_name().to_string_lossy().ends_with(".txt") {
+                paths.push(file.path());
+            }
+        }
+        paths.sort();
+        let mut library = BoardLibrary::default();
+        for path in paths {
+            match BoardInfo::read(&path) {
+                Ok(info) => library.boards.push(info),
+                Err(e) => library.problems.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        Ok(library)
+    }
+
+    /// Finds a board by its name in any case, its file name with or without
+    /// `.txt`, or the path to any board file
+    pub fn find(&self, name_or_path: &str) -> Result<BoardInfo, crate::Error> {
+        let found = self.boards.iter().find(|info| {
+            info.name.eq_ignore_ascii_case(name_or_path)
+                || info
+                    .path
+                    .file_stem()
+                    .is_some_and(|stem| stem == name_or_path)
+                || info
+                    .path
+                    .file_name()
+                    .is_some_and(|name| name == name_or_path)
+        });
+        if let Some(info) = found {
+            return Ok(info.clone());
+        }
+        let path = Path::new(name_or_path);
+        if path.is_file() {
+            return BoardInfo::read(path);
+        }
+        Err(BoardNotFoundError {
+            name: name_or_path.to_string(),
+        }
+        .into())
+    }
+}
+
+#[derive(Debug)]
+pub struct BoardNotFoundError {
+    name: String,
+}
+
+impl Display for BoardNotFoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Board not found: {}", self.name)
+    }
+}
+
+impl std::error::Error for BoardNotFoundError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, Expect};
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    #[test]
+    fn lists_shipped_boards() {
+        let library = BoardLibrary::scan("./chess_boards/").unwrap();
+        let lines = library
+            .boards
+            .iter()
+            .map(|info| format!("{} from {}", info, info.path.display()))
+            .collect::<Vec<_>>();
+        check(
+            expect![[r#"
+            Classic Chess (8x8, 2 players) from ./chess_boards/classic_chess.txt
+            Four Player Chess (14x14, 4 players) from ./chess_boards/four_player_chess.txt"#]],
+            lines.join("\n"),
+        );
+    }
+
+    #[test]
+    fn sets_aside_files_that_are_not_boards() {
+        let dir = std::env::temp_dir().join(format!(
+            "chess-variants-board-library-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(
+            "./chess_boards/classic_chess.txt",
+            dir.join("classic_chess.txt"),
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "Size: eight\n").unwrap();
+        let library = BoardLibrary::scan(&dir.to_string_lossy()).unwrap();
+        let problems = library
+            .problems
+            .iter()
+            .map(|problem| problem.replace(&dir.to_string_lossy().to_string(), "<dir>"))
+            .collect::<Vec<_>>();
+        fs::remove_dir_all(&dir).unwrap();
+        check(
+            expect![[r#"
+                Classic Chess (8x8, 2 players)
+                <dir>/notes.txt: Invalid file format (line 1): 'eight'"#]],
+            format!(
+                "{}\n{}",
+                library
+                    .boards
+                    .iter()
+                    .map(|info| info.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                problems.join("\n")
+            ),
+        );
+    }
+
+    #[test]
+    fn finds_boards_by_name_file_or_path() {
+        let library = BoardLibrary::scan("./chess_boards/").unwrap();
+        let find = |name: &str| match library.find(name) {
+            Ok(info) => info.to_string(),
+            Err(e) => e.to_string(),
+        };
+        check(
+            expect![[r#"
+                Four Player Chess (14x14, 4 players)
+                Classic Chess (8x8, 2 players)
+                Classic Chess (8x8, 2 players)
+                Four Player Chess (14x14, 4 players)
+                Invalid file format (line 5): '('
+                Board not found: Shogi"#]],
+            [
+                "four player chess",
+                "classic_chess",
+                "classic_chess.txt",
+                "./chess_boards/four_player_chess.txt",
+                "./chess_boards/Z-BoardFileFormat",
+                "Shogi",
+            ]
+            .iter()
+            .map(|name| find(name))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        );
+    }
+}
@@ -2,6 +2,7 @@ use sdl2::pixels::Color;
 
 use super::game_piece::GamePiece;
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct BoardSpace {
     pub horz_position: u32,
     pub vert_position: u32,
@@ -29,7 +30,6 @@ impl BoardSpace {
         })
     }
 
-    #[allow(dead_code)]
     pub fn reset_status(&mut self) {
         self.available_to_move = false;
         self.available_to_kill = false;
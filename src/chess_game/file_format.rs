@@ -0,0 +1,8 @@
+/// The dashed line piece and board files use to split statements into groups
+pub const SEPARATOR: &str = "----------------------------";
+
+/// Writes a name as one word: spaces become underscores, which both file
+/// formats read back as spaces
+pub fn file_word(text: &str) -> String {
+    text.replace(' ', "_")
+}
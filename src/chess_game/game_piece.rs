@@ -1,8 +1,10 @@
+#[derive(Debug, Clone, PartialEq)]
 pub struct GamePiece {
     pub piece_name: String, // Needs to be reference (lifetime?)
     pub team_name: String,
     pub horz_position: u32,
     pub vert_position: u32,
+    pub has_moved: bool,
 }
 
 impl GamePiece {
@@ -17,6 +19,7 @@ impl GamePiece {
             team_name: team,
             horz_position: horz,
             vert_position: vert,
+            has_moved: false,
         })
     }
 }
@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+/// The state of the game from the point of view of the side about to move
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    /// The side to move has a royal piece under attack but can escape
+    Check {
+        team: String,
+    },
+    /// The side to move is in check with no legal moves
+    Checkmate {
+        loser: String,
+    },
+    /// The side to move is not in check but has no legal moves
+    Stalemate {
+        team: String,
+    },
+    /// The side has nothing left but its royal pieces, on a board where that loses
+    BareRoyal {
+        loser: String,
+    },
+}
+
+impl GameStatus {
+    /// Whether the game has ended, so no more moves may be made
+    pub fn is_over(&self) -> bool {
+        matches!(
+            self,
+            GameStatus::Checkmate { .. }
+                | GameStatus::Stalemate { .. }
+                | GameStatus::BareRoyal { .. }
+        )
+    }
+}
+
+impl Display for GameStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameStatus::Ongoing => write!(f, "Ongoing"),
+            GameStatus::Check { team } => write!(f, "{} is in check", team),
+            GameStatus::Checkmate { loser } => write!(f, "Checkmate, {} loses", loser),
+            GameStatus::Stalemate { team } => write!(f, "Stalemate, {} cannot move", team),
+            GameStatus::BareRoyal { loser } => {
+                write!(f, "{} has only royal pieces left and loses", loser)
+            }
+        }
+    }
+}
@@ -0,0 +1,1119 @@
+use super::board::Board;
+use super::game_piece::GamePiece;
+use super::game_status::GameStatus;
+use super::piece_catalog::PieceCatalog;
+use super::piece_move::{MoveRules, PieceMove, DEFAULT_PAWN_FIRST_DISTANCE};
+use super::player::Orientation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveKind {
+    Move,
+    Capture,
+    /// A first move of several `step`s, leaving the squares passed over open to en passant
+    PawnFirst {
+        step: (i32, i32),
+    },
+    /// A capture onto a passed-over square, removing the piece at `captured`
+    EnPassant {
+        captured: (u32, u32),
+    },
+    /// The royal piece moves to `to` while its partner jumps to the inner side of it
+    Castle {
+        partner_from: (u32, u32),
+        partner_to: (u32, u32),
+    },
+}
+
+/// A single destination a piece can reach, in 1-based board coordinates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardMove {
+    pub from: (u32, u32),
+    pub to: (u32, u32),
+    pub kind: MoveKind,
+}
+
+/// Expands the `PieceMove`s of pieces on a board into pseudo-legal `BoardMove`s
+///
+/// Looking ahead does not copy the board: `vacated` squares are treated as
+/// empty and `placed` pieces as standing on their squares, over whatever the
+/// board holds there.
+#[derive(Clone)]
+pub struct MoveGenerator<'a> {
+    board: &'a Board,
+    catalog: &'a PieceCatalog,
+    vacated: Vec<(u32, u32)>,
+    placed: Vec<GamePiece>,
+}
+
+impl<'a> MoveGenerator<'a> {
+    pub fn new(board: &'a Board, catalog: &'a PieceCatalog) -> Self {
+        Self {
+            board,
+            catalog,
+            vacated: vec![],
+            placed: vec![],
+        }
+    }
+
+    pub fn piece_moves(&self, game_piece: &GamePiece) -> Result<Vec<BoardMove>, crate::Error> {
+        let piece = self.catalog.get_piece(&game_piece.piece_name)?;
+        let mut moves = vec![];
+        for piece_move in &piece.move_set {
+            self.add_moves(game_piece, piece_move, &mut moves)?;
+        }
+        Ok(moves)
+    }
+
+    /// Moves for the piece that do not leave a royal piece of its team attacked
+    pub fn legal_moves(&self, game_piece: &GamePiece) -> Result<Vec<BoardMove>, crate::Error> {
+        let mut legal = vec![];
+        for board_move in self.piece_moves(game_piece)? {
+            if let MoveKind::Castle { .. } = board_move.kind {
+                // The royal piece may not castle out of, through or into an attack
+                let path = Self::span(board_move.from, board_move.to);
+                if self.is_attacked(&game_piece.team_name, &path)? {
+                    continue;
+                }
+            }
+            if !self.leaves_royal_attacked(game_piece, &board_move)? {
+                legal.push(board_move);
+            }
+        }
+        Ok(legal)
+    }
+
+    pub fn team_legal_moves(&self, team_name: &str) -> Result<Vec<BoardMove>, crate::Error> {
+        let mut moves = vec![];
+        for game_piece in self.pieces() {
+            if game_piece.team_name == team_name {
+                moves.append(&mut self.legal_moves(game_piece)?);
+            }
+        }
+        Ok(moves)
+    }
+
+    pub fn is_in_check(&self, team_name: &str) -> Result<bool, crate::Error> {
+        let mut royal_positions = vec![];
+        for game_piece in self.pieces() {
+            if game_piece.team_name == team_name
+                && self.catalog.get_piece(&game_piece.piece_name)?.royal
+            {
+                royal_positions.push((game_piece.horz_position, game_piece.vert_position));
+            }
+        }
+        self.is_attacked(team_name, &royal_positions)
+    }
+
+    /// Whether any other team could capture a piece of `team_name` standing on
+    /// one of the positions
+    pub fn is_attacked(
+        &self,
+        team_name: &str,
+        positions: &[(u32, u32)],
+    ) -> Result<bool, crate::Error> {
+        if positions.is_empty() {
+            return Ok(false);
+        }
+        for &(horz, vert) in positions {
+            // Capture-only moves need a target, so an empty square gets a stand-in.
+            // Each square is checked alone so stand-ins never screen for each other.
+            let mut generator = self.clone();
+            if generator.occupant((horz, vert)).is_none() {
+                generator.placed.push(GamePiece::new(
+                    String::new(),
+                    team_name.to_string(),
+                    horz,
+                    vert,
+                )?);
+            }
+            for game_piece in generator.pieces() {
+                if game_piece.team_name == team_name {
+                    continue;
+                }
+                let attacks = generator.piece_moves(game_piece)?.iter().any(|board_move| {
+                    board_move.kind == MoveKind::Capture && board_move.to == (horz, vert)
+                });
+                if attacks {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn game_status(&self, team_name: &str) -> Result<GameStatus, crate::Error> {
+        let in_check = self.is_in_check(team_name)?;
+        if self.team_legal_moves(team_name)?.is_empty() {
+            return Ok(if in_check {
+                GameStatus::Checkmate {
+                    loser: team_name.to_string(),
+                }
+            } else {
+                GameStatus::Stalemate {
+                    team: team_name.to_string(),
+                }
+            });
+        }
+        if self.board.bare_royal_loses && self.is_bare(team_name)? {
+            let mut opponents_bare = true;
+            for player in &self.board.players {
+                if player.name != team_name && !self.is_bare(&player.name)? {
+                    opponents_bare = false;
+                }
+            }
+            if !opponents_bare {
+                return Ok(GameStatus::BareRoyal {
+                    loser: team_name.to_string(),
+                });
+            }
+        }
+        if in_check {
+            return Ok(GameStatus::Check {
+                team: team_name.to_string(),
+            });
+        }
+        Ok(GameStatus::Ongoing)
+    }
+
+    /// Whether the team has royal pieces and nothing else
+    fn is_bare(&self, team_name: &str) -> Result<bool, crate::Error> {
+        let mut has_royal = false;
+        for game_piece in self.pieces() {
+            if game_piece.team_name != team_name {
+                continue;
+            }
+            if self.catalog.get_piece(&game_piece.piece_name)?.royal {
+                has_royal = true;
+            } else {
+                return Ok(false);
+            }
+        }
+        Ok(has_royal)
+    }
+
+    /// Whether the team is in check once `game_piece` makes the move
+    fn leaves_royal_attacked(
+        &self,
+        game_piece: &GamePiece,
+        board_move: &BoardMove,
+    ) -> Result<bool, crate::Error> {
+        let mut after = self.clone();
+        after.vacated.push(board_move.from);
+        after
+            .placed
+            .push(Self::moved_piece(game_piece, board_move.to));
+        match board_move.kind {
+            MoveKind::EnPassant { captured } => after.vacated.push(captured),
+            MoveKind::Castle {
+                partner_from,
+                partner_to,
+            } => {
+                after.vacated.push(partner_from);
+                if let Some(partner) = self.occupant(partner_from) {
+                    after.placed.push(Self::moved_piece(partner, partner_to));
+                }
+            }
+            _ => {}
+        }
+        after.is_in_check(&game_piece.team_name)
+    }
+
+    fn moved_piece(game_piece: &GamePiece, (horz, vert): (u32, u32)) -> GamePiece {
+        let mut moved = game_piece.clone();
+        moved.horz_position = horz;
+        moved.vert_position = vert;
+        moved.has_moved = true;
+        moved
+    }
+
+    /// The piece standing on the square, taking the look-ahead into account
+    fn occupant(&self, (horz, vert): (u32, u32)) -> Option<&GamePiece> {
+        let placed = self.placed.iter().rev().find(|game_piece| {
+            (game_piece.horz_position, game_piece.vert_position) == (horz, vert)
+        });
+        if placed.is_some() || self.vacated.contains(&(horz, vert)) {
+            return placed;
+        }
+        self.board.get_piece(horz, vert)
+    }
+
+    /// Every piece on the board, taking the look-ahead into account
+    fn pieces(&self) -> Vec<&GamePiece> {
+        self.placed
+            .iter()
+            .chain(self.board.collect_game_pieces())
+            .filter(|game_piece| {
+                let position = (game_piece.horz_position, game_piece.vert_position);
+                self.occupant(position)
+                    .is_some_and(|occupant| std::ptr::eq(occupant, *game_piece))
+            })
+            .collect()
+    }
+
+    fn is_vacant(&self, position: (u32, u32)) -> bool {
+        self.board.is_active(position) && self.occupant(position).is_none()
+    }
+
+    fn direction(&self, game_piece: &GamePiece, piece_move: &PieceMove) -> (i32, i32) {
+        self.board
+            .orientation(&game_piece.team_name)
+            .resolve(piece_move.forward, piece_move.left)
+    }
+
+    fn add_moves(
+        &self,
+        game_piece: &GamePiece,
+        piece_move: &PieceMove,
+        moves: &mut Vec<BoardMove>,
+    ) -> Result<(), crate::Error> {
+        match piece_move.rules {
+            MoveRules::Castle => return self.add_castle_moves(game_piece, moves),
+            MoveRules::PawnFirst => {
+                return self.add_pawn_first_moves(game_piece, piece_move, moves)
+            }
+            _ => {}
+        }
+        if !piece_move.legs.is_empty() {
+            return self.add_multi_leg_moves(game_piece, piece_move, moves);
+        }
+        let from = (game_piece.horz_position, game_piece.vert_position);
+        self.add_leg_moves(game_piece, from, piece_move, moves);
+        Ok(())
+    }
+
+    /// Walks a move leg by leg; every leg but the last must stop on empty squares
+    fn add_multi_leg_moves(
+        &self,
+        game_piece: &GamePiece,
+        piece_move: &PieceMove,
+        moves: &mut Vec<BoardMove>,
+    ) -> Result<(), crate::Error> {
+        let from = (game_piece.horz_position, game_piece.vert_position);
+        // The piece has left its square while it is on the way
+        let mut generator = self.clone();
+        generator.vacated.push(from);
+        let mut first_leg = piece_move.clone();
+        first_leg.legs.clear();
+        let (last_leg, middle_legs) = piece_move.legs.split_last().unwrap();
+        let mut stops = vec![from];
+        for leg in std::iter::once(&first_leg).chain(middle_legs) {
+            let mut next_stops = vec![];
+            for &stop in &stops {
+                let mut leg_moves = vec![];
+                generator.add_leg_moves(game_piece, stop, leg, &mut leg_moves);
+                for leg_move in leg_moves {
+                    if leg_move.kind == MoveKind::Move && !next_stops.contains(&leg_move.to) {
+                        next_stops.push(leg_move.to);
+                    }
+                }
+            }
+            stops = next_stops;
+        }
+        for stop in stops {
+            let mut leg_moves = vec![];
+            generator.add_leg_moves(game_piece, stop, last_leg, &mut leg_moves);
+            for leg_move in leg_moves {
+                if leg_move.to != from {
+                    Self::push_move(moves, from, leg_move.to, leg_move.kind);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The moves of a single leg made from `from`
+    fn add_leg_moves(
+        &self,
+        game_piece: &GamePiece,
+        from: (u32, u32),
+        piece_move: &PieceMove,
+        moves: &mut Vec<BoardMove>,
+    ) {
+        let direction = self.direction(game_piece, piece_move);
+        if direction == (0, 0) {
+            return;
+        }
+        match piece_move.rules {
+            MoveRules::Leap => {
+                if let Some(to) = self.board.offset_position(from, direction) {
+                    if self.is_vacant(to) {
+                        Self::push_move(moves, from, to, MoveKind::Move);
+                    }
+                }
+            }
+            MoveRules::Kill => {
+                if let Some(to) = self.board.offset_position(from, direction) {
+                    if self.is_enemy(game_piece, to) {
+                        Self::push_move(moves, from, to, MoveKind::Capture);
+                    } else if let Some(en_passant) = &self.board.en_passant {
+                        if en_passant.team_name != game_piece.team_name
+                            && en_passant.squares.contains(&to)
+                            && self.is_vacant(to)
+                        {
+                            Self::push_move(
+                                moves,
+                                from,
+                                to,
+                                MoveKind::EnPassant {
+                                    captured: en_passant.piece_position,
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            MoveRules::Run | MoveRules::Slide => {
+                let mut current = from;
+                let mut distance = 0;
+                while let Some(to) = self.board.offset_position(current, direction) {
+                    distance += 1;
+                    if piece_move.max_distance.is_some_and(|max| distance > max) {
+                        break;
+                    }
+                    if !self.board.is_active(to) {
+                        if !self.board.runs_jump_holes {
+                            break;
+                        }
+                        current = to;
+                        continue;
+                    }
+                    let in_range = distance >= piece_move.min_distance;
+                    if !self.is_vacant(to) {
+                        if in_range
+                            && piece_move.rules == MoveRules::Run
+                            && self.is_enemy(game_piece, to)
+                        {
+                            Self::push_move(moves, from, to, MoveKind::Capture);
+                        }
+                        break;
+                    }
+                    if in_range {
+                        Self::push_move(moves, from, to, MoveKind::Move);
+                    }
+                    current = to;
+                }
+            }
+            MoveRules::Hop => {
+                // Jumps the first piece in the way and lands right behind it
+                let mut squares = self
+                    .ray(from, direction)
+                    .into_iter()
+                    .skip_while(|&square| self.is_vacant(square));
+                if let (Some(_screen), Some(to)) = (squares.next(), squares.next()) {
+                    if self.is_vacant(to) {
+                        Self::push_move(moves, from, to, MoveKind::Move);
+                    } else if self.is_enemy(game_piece, to) {
+                        Self::push_move(moves, from, to, MoveKind::Capture);
+                    }
+                }
+            }
+            MoveRules::CannonKill => {
+                // Jumps the first piece in the way and captures the next one behind it
+                let mut pieces = self
+                    .ray(from, direction)
+                    .into_iter()
+                    .filter(|&square| !self.is_vacant(square));
+                if let (Some(_screen), Some(to)) = (pieces.next(), pieces.next()) {
+                    if self.is_enemy(game_piece, to) {
+                        Self::push_move(moves, from, to, MoveKind::Capture);
+                    }
+                }
+            }
+            MoveRules::PawnFirst | MoveRules::Castle => unreachable!(),
+        }
+    }
+
+    /// The active squares from `from` outwards until the edge of the board, or
+    /// until a disabled square when runs stop at holes
+    fn ray(&self, from: (u32, u32), direction: (i32, i32)) -> Vec<(u32, u32)> {
+        let mut squares = vec![];
+        let mut current = from;
+        while let Some(to) = self.board.offset_position(current, direction) {
+            current = to;
+            if self.board.is_active(to) {
+                squares.push(to);
+            } else if !self.board.runs_jump_holes {
+                break;
+            }
+        }
+        squares
+    }
+
+    /// Repeats each of the piece's `Leap`s up to `piece_move.max_distance` times for an
+    /// unmoved piece, as long as every square along the way is empty
+    fn add_pawn_first_moves(
+        &self,
+        game_piece: &GamePiece,
+        piece_move: &PieceMove,
+        moves: &mut Vec<BoardMove>,
+    ) -> Result<(), crate::Error> {
+        if game_piece.has_moved {
+            return Ok(());
+        }
+        let from = (game_piece.horz_position, game_piece.vert_position);
+        let piece = self.catalog.get_piece(&game_piece.piece_name)?;
+        for leap in piece.move_set.iter().filter(|m| m.rules == MoveRules::Leap) {
+            let step = self.direction(game_piece, leap);
+            if step == (0, 0) {
+                continue;
+            }
+            let mut current = from;
+            let reach = piece_move
+                .max_distance
+                .unwrap_or(DEFAULT_PAWN_FIRST_DISTANCE);
+            for distance in 1..=reach {
+                match self.board.offset_position(current, step) {
+                    Some(to) if self.is_vacant(to) => {
+                        if distance > 1 {
+                            Self::push_move(moves, from, to, MoveKind::PawnFirst { step });
+                        }
+                        current = to;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Castling towards every unmoved partner on the royal piece's sideways line
+    ///
+    /// Destinations are counted from the last active square behind the partner,
+    /// as in Chess960: towards the high end the royal piece lands on the second
+    /// square from that edge, towards the low end on the third, with the partner
+    /// on its inner side. This keeps the classic c/g squares on wider boards and
+    /// on the arms of cross-shaped ones.
+    fn add_castle_moves(
+        &self,
+        game_piece: &GamePiece,
+        moves: &mut Vec<BoardMove>,
+    ) -> Result<(), crate::Error> {
+        if game_piece.has_moved {
+            return Ok(());
+        }
+        let horizontal = match self.board.orientation(&game_piece.team_name) {
+            Orientation::Up | Orientation::Down => true,
+            Orientation::Left | Orientation::Right => false,
+            _ => return Ok(()),
+        };
+        let from = (game_piece.horz_position, game_piece.vert_position);
+        let (along, across) = if horizontal {
+            (from.0, from.1)
+        } else {
+            (from.1, from.0)
+        };
+        let position = |along: u32| {
+            if horizontal {
+                (along, across)
+            } else {
+                (across, along)
+            }
+        };
+        let mut low_edge = along;
+        while low_edge > 1 && self.board.is_active(position(low_edge - 1)) {
+            low_edge -= 1;
+        }
+        let mut high_edge = along;
+        while self.board.is_active(position(high_edge + 1)) {
+            high_edge += 1;
+        }
+        for partner in self.pieces() {
+            let (partner_along, partner_across) = if horizontal {
+                (partner.horz_position, partner.vert_position)
+            } else {
+                (partner.vert_position, partner.horz_position)
+            };
+            if partner.team_name != game_piece.team_name
+                || partner.has_moved
+                || partner_across != across
+                || partner_along == along
+                || !self.catalog.get_piece(&partner.piece_name)?.castle_partner
+            {
+                continue;
+            }
+            let (royal_to, partner_to) = if partner_along > along {
+                (high_edge.saturating_sub(1), high_edge.saturating_sub(2))
+            } else {
+                (low_edge + 2, low_edge + 3)
+            };
+            let on_line = |step: u32| step >= low_edge && step <= high_edge;
+            if !on_line(royal_to) || !on_line(partner_to) || !on_line(partner_along) {
+                continue;
+            }
+            let lowest = along.min(partner_along).min(royal_to).min(partner_to);
+            let highest = along.max(partner_along).max(royal_to).max(partner_to);
+            let path_clear = (lowest..=highest).all(|step| {
+                step == along || step == partner_along || self.is_vacant(position(step))
+            });
+            if path_clear {
+                Self::push_move(
+                    moves,
+                    from,
+                    position(royal_to),
+                    MoveKind::Castle {
+                        partner_from: position(partner_along),
+                        partner_to: position(partner_to),
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Every square on the straight line from `from` to `to`, both included
+    fn span(from: (u32, u32), to: (u32, u32)) -> Vec<(u32, u32)> {
+        let step = |a: u32, b: u32| (b as i64 - a as i64).signum();
+        let (horz_step, vert_step) = (step(from.0, to.0), step(from.1, to.1));
+        let mut squares = vec![from];
+        let mut current = from;
+        while current != to {
+            current = (
+                (current.0 as i64 + horz_step) as u32,
+                (current.1 as i64 + vert_step) as u32,
+            );
+            squares.push(current);
+        }
+        squares
+    }
+
+    fn is_enemy(&self, game_piece: &GamePiece, position: (u32, u32)) -> bool {
+        match self.occupant(position) {
+            Some(other) => other.team_name != game_piece.team_name,
+            None => false,
+        }
+    }
+
+    fn push_move(moves: &mut Vec<BoardMove>, from: (u32, u32), to: (u32, u32), kind: MoveKind) {
+        let board_move = BoardMove { from, to, kind };
+        if !moves.contains(&board_move) {
+            moves.push(board_move);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_game::betza;
+    use crate::chess_game::piece::Piece;
+    use crate::chess_game::player::Orientation;
+    use crate::chess_game::test_support::{board, catalog};
+    use expect_test::{expect, Expect};
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    fn destinations(board: &Board, catalog: &PieceCatalog, horz: u32, vert: u32) -> String {
+        let generator = MoveGenerator::new(board, catalog);
+        let mut moves = generator
+            .piece_moves(board.get_piece(horz, vert).unwrap())
+            .unwrap();
+        moves.sort_by_key(|m| m.to);
+        moves
+            .iter()
+            .map(|m| format!("{:?} {:?}", m.to, m.kind))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn rook_runs_until_blocked() {
+        let catalog = catalog();
+        let board = board(&[
+            ("Rook", "white", 1, 1),
+            ("Pawn", "white", 1, 3),
+            ("Knight", "black", 4, 1),
+        ]);
+        check(
+            expect![[r#"
+                (1, 2) Move
+                (2, 1) Move
+                (3, 1) Move
+                (4, 1) Capture"#]],
+            destinations(&board, &catalog, 1, 1),
+        );
+    }
+
+    #[test]
+    fn pawn_leaps_and_kills_separately() {
+        let catalog = catalog();
+        let board = board(&[
+            ("Pawn", "white", 4, 2),
+            ("Pawn", "black", 4, 3),
+            ("Pawn", "black", 5, 3),
+            ("Pawn", "white", 3, 3),
+        ]);
+        check(
+            expect![[r#"
+                (5, 3) Capture"#]],
+            destinations(&board, &catalog, 4, 2),
+        );
+    }
+
+    #[test]
+    fn knight_stays_on_board() {
+        let catalog = catalog();
+        let board = board(&[("Knight", "white", 2, 1), ("Bishop", "black", 3, 3)]);
+        check(
+            expect![[r#"
+                (1, 3) Move
+                (3, 3) Capture
+                (4, 2) Move"#]],
+            destinations(&board, &catalog, 2, 1),
+        );
+    }
+
+    #[test]
+    fn black_pawn_moves_down_the_board() {
+        let catalog = catalog();
+        let board = board(&[
+            ("Pawn", "black", 4, 7),
+            ("Knight", "white", 3, 6),
+            ("Knight", "white", 5, 8),
+        ]);
+        check(
+            expect![[r#"
+                (3, 6) Capture
+                (4, 5) PawnFirst { step: (0, -1) }
+                (4, 6) Move"#]],
+            destinations(&board, &catalog, 4, 7),
+        );
+    }
+
+    #[test]
+    fn diagonal_orientation_turns_moves_an_eighth() {
+        assert_eq!(Orientation::UpRight.resolve(1, 0), (1, 1));
+        assert_eq!(Orientation::UpRight.resolve(1, 1), (0, 1));
+        assert_eq!(Orientation::UpRight.resolve(1, -1), (1, 0));
+        assert_eq!(Orientation::DownLeft.resolve(1, 0), (-1, -1));
+        assert_eq!(Orientation::UpLeft.resolve(2, 0), (-2, 2));
+        assert_eq!(Orientation::Left.resolve(1, 1), (-1, -1));
+    }
+
+    #[test]
+    fn pinned_piece_cannot_expose_king() {
+        let catalog = catalog();
+        let board = board(&[
+            ("King", "white", 5, 1),
+            ("Bishop", "white", 5, 2),
+            ("Rook", "black", 5, 8),
+        ]);
+        let generator = MoveGenerator::new(&board, &catalog);
+        let bishop = board.get_piece(5, 2).unwrap();
+        assert!(generator.legal_moves(bishop).unwrap().is_empty());
+        assert!(!generator.piece_moves(bishop).unwrap().is_empty());
+    }
+
+    #[test]
+    fn king_cannot_hide_behind_itself() {
+        let catalog = catalog();
+        let board = board(&[
+            ("King", "white", 5, 1),
+            ("Rook", "black", 1, 1),
+            ("Rook", "black", 4, 2),
+            ("King", "black", 5, 8),
+        ]);
+        let generator = MoveGenerator::new(&board, &catalog);
+        let moves = generator
+            .legal_moves(board.get_piece(5, 1).unwrap())
+            .unwrap();
+        check(
+            expect![[r#"
+                [
+                    BoardMove {
+                        from: (
+                            5,
+                            1,
+                        ),
+                        to: (
+                            4,
+                            2,
+                        ),
+                        kind: Capture,
+                    },
+                ]"#]],
+            format!("{:#?}", moves),
+        );
+    }
+
+    #[test]
+    fn detects_checkmate_and_stalemate() {
+        let catalog = catalog();
+        let mate = board(&[
+            ("King", "black", 1, 8),
+            ("Rook", "white", 8, 8),
+            ("Rook", "white", 7, 7),
+            ("King", "white", 5, 1),
+        ]);
+        check(
+            expect![[r#"
+                Checkmate {
+                    loser: "black",
+                }"#]],
+            format!(
+                "{:#?}",
+                MoveGenerator::new(&mate, &catalog)
+                    .game_status("black")
+                    .unwrap()
+            ),
+        );
+        let stalemate = board(&[
+            ("King", "black", 1, 8),
+            ("Queen", "white", 2, 6),
+            ("King", "white", 5, 1),
+        ]);
+        check(
+            expect![[r#"
+                Stalemate {
+                    team: "black",
+                }"#]],
+            format!(
+                "{:#?}",
+                MoveGenerator::new(&stalemate, &catalog)
+                    .game_status("black")
+                    .unwrap()
+            ),
+        );
+    }
+
+    fn castles(board: &Board, catalog: &PieceCatalog, horz: u32, vert: u32) -> String {
+        let generator = MoveGenerator::new(board, catalog);
+        let mut moves = generator
+            .legal_moves(board.get_piece(horz, vert).unwrap())
+            .unwrap();
+        moves.retain(|m| matches!(m.kind, MoveKind::Castle { .. }));
+        moves.sort_by_key(|m| m.to);
+        moves
+            .iter()
+            .map(|m| format!("{:?} {:?}", m.to, m.kind))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn castles_on_both_sides() {
+        let catalog = catalog();
+        let board = board(&[
+            ("Rook", "white", 1, 1),
+            ("King", "white", 5, 1),
+            ("Rook", "white", 8, 1),
+            ("King", "black", 5, 8),
+        ]);
+        check(
+            expect![[r#"
+                (3, 1) Castle { partner_from: (1, 1), partner_to: (4, 1) }
+                (7, 1) Castle { partner_from: (8, 1), partner_to: (6, 1) }"#]],
+            castles(&board, &catalog, 5, 1),
+        );
+    }
+
+    #[test]
+    fn cannot_castle_blocked_moved_or_through_attack() {
+        let catalog = catalog();
+        let mut board = board(&[
+            ("Rook", "white", 1, 1),
+            ("Knight", "white", 2, 1),
+            ("King", "white", 5, 1),
+            ("Rook", "white", 8, 1),
+            ("Rook", "black", 6, 8),
+            ("King", "black", 1, 8),
+        ]);
+        check(expect![[""]], castles(&board, &catalog, 5, 1));
+
+        board.take_piece(6, 8);
+        board.move_piece((8, 1), (8, 2));
+        board.move_piece((8, 2), (8, 1));
+        check(expect![[""]], castles(&board, &catalog, 5, 1));
+    }
+
+    #[test]
+    fn castles_from_shuffled_setup() {
+        let catalog = catalog();
+        let mut board = board(&[
+            ("Rook", "white", 1, 1),
+            ("King", "white", 2, 1),
+            ("Rook", "white", 7, 1),
+            ("King", "black", 5, 8),
+        ]);
+        check(
+            expect![[r#"
+                (3, 1) Castle { partner_from: (1, 1), partner_to: (4, 1) }
+                (7, 1) Castle { partner_from: (7, 1), partner_to: (6, 1) }"#]],
+            castles(&board, &catalog, 2, 1),
+        );
+        let castle = MoveGenerator::new(&board, &catalog)
+            .legal_moves(board.get_piece(2, 1).unwrap())
+            .unwrap()
+            .into_iter()
+            .find(|m| m.to == (3, 1) && matches!(m.kind, MoveKind::Castle { .. }))
+            .unwrap();
+        board.apply_move(&castle);
+        assert_eq!(board.get_piece(3, 1).unwrap().piece_name, "King");
+        assert_eq!(board.get_piece(4, 1).unwrap().piece_name, "Rook");
+        assert!(board.get_piece(1, 1).is_none());
+        assert!(board.get_piece(2, 1).is_none());
+    }
+
+    #[test]
+    fn pawn_first_move_and_en_passant() {
+        let catalog = catalog();
+        let mut board = board(&[("Pawn", "white", 5, 2), ("Pawn", "black", 4, 5)]);
+        board.move_piece((4, 5), (4, 4));
+        check(
+            expect![[r#"
+            (5, 3) Move
+            (5, 4) PawnFirst { step: (0, 1) }"#]],
+            destinations(&board, &catalog, 5, 2),
+        );
+
+        let double_step = MoveGenerator::new(&board, &catalog)
+            .piece_moves(board.get_piece(5, 2).unwrap())
+            .unwrap()
+            .into_iter()
+            .find(|m| m.to == (5, 4))
+            .unwrap();
+        board.apply_move(&double_step);
+        check(
+            expect![[r#"
+            (4, 3) Move
+            (5, 3) EnPassant { captured: (5, 4) }"#]],
+            destinations(&board, &catalog, 4, 4),
+        );
+
+        let en_passant = MoveGenerator::new(&board, &catalog)
+            .piece_moves(board.get_piece(4, 4).unwrap())
+            .unwrap()
+            .into_iter()
+            .find(|m| m.to == (5, 3))
+            .unwrap();
+        assert_eq!(board.apply_move(&en_passant).unwrap().team_name, "white");
+        assert!(board.get_piece(5, 4).is_none());
+        assert!(board.en_passant.is_none());
+    }
+
+    #[test]
+    fn pawn_first_move_is_blocked_and_used_up() {
+        let catalog = catalog();
+        let mut board = board(&[("Pawn", "white", 2, 2), ("Knight", "black", 2, 3)]);
+        check(expect![[""]], destinations(&board, &catalog, 2, 2));
+
+        board.move_piece((2, 3), (8, 8));
+        board.move_piece((2, 2), (2, 3));
+        check(
+            expect![[r#"(2, 4) Move"#]],
+            destinations(&board, &catalog, 2, 3),
+        );
+    }
+
+    #[test]
+    fn runs_stop_at_or_jump_disabled_squares() {
+        let catalog = catalog();
+        let mut board = board(&[("Rook", "white", 1, 1)]);
+        let hole = board.find_board_space_index(4, 1) as usize;
+        board.grid[hole].is_active = false;
+        board.place_piece(GamePiece::new("Pawn".to_string(), "white".to_string(), 1, 2).unwrap());
+        check(
+            expect![[r#"
+            (2, 1) Move
+            (3, 1) Move"#]],
+            destinations(&board, &catalog, 1, 1),
+        );
+
+        board.runs_jump_holes = true;
+        check(
+            expect![[r#"
+            (2, 1) Move
+            (3, 1) Move
+            (5, 1) Move
+            (6, 1) Move
+            (7, 1) Move
+            (8, 1) Move"#]],
+            destinations(&board, &catalog, 1, 1),
+        );
+    }
+
+    #[test]
+    fn leaps_cannot_land_on_disabled_squares() {
+        let catalog = catalog();
+        let mut board = board(&[("Knight", "white", 1, 1)]);
+        let hole = board.find_board_space_index(2, 3) as usize;
+        board.grid[hole].is_active = false;
+        board.place_piece(GamePiece::new("Pawn".to_string(), "black".to_string(), 2, 3).unwrap());
+        check(
+            expect![[r#"(3, 2) Move"#]],
+            destinations(&board, &catalog, 1, 1),
+        );
+    }
+
+    #[test]
+    fn runs_respect_min_and_max_distance() {
+        let mut catalog = catalog();
+        let mut short_rook = Piece::new();
+        short_rook.name = "ShortRook".to_string();
+        let mut up = PieceMove::new(1, 0, MoveRules::Run);
+        up.max_distance = Some(3);
+        let mut right = PieceMove::new(0, -1, MoveRules::Run);
+        right.min_distance = 2;
+        short_rook.move_set = vec![up, right];
+        catalog.catalog.insert(short_rook.name.clone(), short_rook);
+        let blocked = board(&[
+            ("ShortRook", "white", 1, 1),
+            ("Pawn", "black", 2, 1),
+            ("Pawn", "black", 1, 3),
+        ]);
+        // The pawn right next to it is too close to take, but still blocks the run
+        check(
+            expect![[r#"
+            (1, 2) Move
+            (1, 3) Capture"#]],
+            destinations(&blocked, &catalog, 1, 1),
+        );
+        let open = board(&[("ShortRook", "white", 1, 1)]);
+        check(
+            expect![[r#"
+            (1, 2) Move
+            (1, 3) Move
+            (1, 4) Move
+            (3, 1) Move
+            (4, 1) Move
+            (5, 1) Move
+            (6, 1) Move
+            (7, 1) Move
+            (8, 1) Move"#]],
+            destinations(&open, &catalog, 1, 1),
+        );
+    }
+
+    fn hopper_catalog() -> PieceCatalog {
+        let mut catalog = catalog();
+        let mut cannon = Piece::new();
+        cannon.name = "Cannon".to_string();
+        cannon.move_set = vec![
+            PieceMove::new(1, 0, MoveRules::CannonKill),
+            PieceMove::new(0, 1, MoveRules::CannonKill),
+        ];
+        catalog.catalog.insert(cannon.name.clone(), cannon);
+        let mut grasshopper = Piece::new();
+        grasshopper.name = "Grasshopper".to_string();
+        grasshopper.move_set = vec![
+            PieceMove::new(1, 0, MoveRules::Hop),
+            PieceMove::new(0, -1, MoveRules::Hop),
+            PieceMove::new(-1, 0, MoveRules::Hop),
+        ];
+        catalog
+            .catalog
+            .insert(grasshopper.name.clone(), grasshopper);
+        catalog
+    }
+
+    #[test]
+    fn cannons_capture_over_one_screen() {
+        let catalog = hopper_catalog();
+        let board = board(&[
+            ("Cannon", "white", 4, 1),
+            ("Pawn", "white", 4, 3),
+            ("Pawn", "black", 4, 6),
+            ("Pawn", "black", 4, 7),
+            ("Pawn", "black", 3, 1),
+            ("Pawn", "black", 2, 1),
+        ]);
+        check(
+            expect![[r#"
+            (2, 1) Capture
+            (4, 6) Capture"#]],
+            destinations(&board, &catalog, 4, 1),
+        );
+    }
+
+    #[test]
+    fn xiangqi_cannons_slide_quietly_and_capture_over_a_screen() {
+        let mut catalog = catalog();
+        let mut cannon = Piece::new();
+        cannon.name = "Xiangqi Cannon".to_string();
+        cannon.move_set = betza::piece_moves("mRcpR").unwrap();
+        catalog.catalog.insert(cannon.name.clone(), cannon);
+        let board = board(&[
+            ("Xiangqi Cannon", "white", 2, 3),
+            ("Pawn", "white", 2, 6),
+            ("Pawn", "black", 2, 8),
+            ("Pawn", "black", 5, 3),
+            ("Pawn", "black", 2, 1),
+        ]);
+        check(
+            expect![[r#"
+            (1, 3) Move
+            (2, 2) Move
+            (2, 4) Move
+            (2, 5) Move
+            (2, 8) Capture
+            (3, 3) Move
+            (4, 3) Move"#]],
+            destinations(&board, &catalog, 2, 3),
+        );
+    }
+
+    #[test]
+    fn grasshoppers_land_behind_the_screen() {
+        let catalog = hopper_catalog();
+        let board = board(&[
+            ("Grasshopper", "white", 4, 4),
+            ("Pawn", "white", 4, 6),
+            ("Pawn", "black", 6, 4),
+            ("Pawn", "black", 7, 4),
+            ("Pawn", "black", 4, 2),
+        ]);
+        check(
+            expect![[r#"
+            (4, 1) Move
+            (4, 7) Move
+            (7, 4) Capture"#]],
+            destinations(&board, &catalog, 4, 4),
+        );
+    }
+
+    #[test]
+    fn cannon_check_needs_a_screen() {
+        let catalog = hopper_catalog();
+        let mut board = board(&[
+            ("King", "white", 5, 1),
+            ("Cannon", "black", 5, 8),
+            ("Pawn", "white", 5, 2),
+        ]);
+        let generator = MoveGenerator::new(&board, &catalog);
+        assert!(generator.is_in_check("white").unwrap());
+        board.take_piece(5, 2);
+        let generator = MoveGenerator::new(&board, &catalog);
+        assert!(!generator.is_in_check("white").unwrap());
+    }
+
+    #[test]
+    fn multi_leg_moves_stop_on_empty_squares() {
+        let mut catalog = catalog();
+        let mut gryphon = Piece::new();
+        gryphon.name = "Gryphon".to_string();
+        // One diagonal step up and to the right, then a slide up or to the right
+        let mut up = PieceMove::new(1, -1, MoveRules::Leap);
+        up.legs = vec![PieceMove::new(1, 0, MoveRules::Run)];
+        let mut right = PieceMove::new(1, -1, MoveRules::Leap);
+        right.legs = vec![PieceMove::new(0, -1, MoveRules::Run)];
+        gryphon.move_set = vec![up, right];
+        catalog.catalog.insert(gryphon.name.clone(), gryphon);
+        let open = board(&[
+            ("Gryphon", "white", 1, 1),
+            ("Pawn", "black", 2, 5),
+            ("Pawn", "white", 5, 2),
+        ]);
+        check(
+            expect![[r#"
+            (2, 3) Move
+            (2, 4) Move
+            (2, 5) Capture
+            (3, 2) Move
+            (4, 2) Move"#]],
+            destinations(&open, &catalog, 1, 1),
+        );
+        let blocked = board(&[("Gryphon", "white", 1, 1), ("Pawn", "white", 2, 2)]);
+        check(expect![[]], destinations(&blocked, &catalog, 1, 1));
+    }
+}
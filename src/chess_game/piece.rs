@@ -1,11 +1,19 @@
 use super::piece_move::PieceMove;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Piece {
     pub name: String,
     pub image_key: String,
     pub move_set: Vec<PieceMove>,
     pub promotions: Vec<String>,
+    pub royal: bool,
+    pub castle_partner: bool,
+    /// The piece this one copies everything from before its own statements
+    pub inherits: Option<String>,
+    /// Pieces whose moves this one also gets
+    pub combines: Vec<String>,
+    /// A value set in the piece file, used instead of the estimated one
+    pub value: Option<u32>,
 }
 
 impl Piece {
@@ -15,6 +23,11 @@ impl Piece {
             image_key: "".to_string(),
             move_set: vec![],
             promotions: vec![],
+            royal: false,
+            castle_partner: false,
+            inherits: None,
+            combines: vec![],
+            value: None,
         }
     }
 }
@@ -1,40 +1,102 @@
 use std::{
-    collections::HashMap,
+    cell::Cell,
+    collections::{HashMap, HashSet},
     fmt::Display,
     fs::{self, File},
-    io::{BufReader, Read},
+    io::{BufRead, BufReader},
     iter::Peekable,
+    ops::RangeInclusive,
+    path::PathBuf,
 };
 
-use super::piece_move::MoveRules;
-use super::InvalidFormatError;
+use super::betza;
+use super::file_format::{file_word, SEPARATOR};
+use super::piece_move::{MoveRules, DEFAULT_PAWN_FIRST_DISTANCE};
 use super::{piece::Piece, piece_move::PieceMove};
 
 #[derive(Debug)]
 pub struct PieceCatalog {
     pub catalog: HashMap<String, Piece>,
+    /// Where each piece was read from
+    sources: HashMap<String, PieceSource>,
+    /// Pieces with a base that is missing or closes a cycle, directly or
+    /// through one of their bases
+    incomplete: HashSet<String>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
+struct PieceSource {
+    file: PathBuf,
+    line: usize,
+    column: usize,
+    references: Vec<PieceReference>,
+    /// Whether a statement of the piece couldn't be read, which was reported already
+    broken: bool,
+}
+
+/// A piece as read from a file, with where its first statement is
+#[derive(Debug)]
+struct ParsedPiece {
+    piece: Piece,
+    line: usize,
+    column: usize,
+    /// The other pieces its statements name, in order
+    references: Vec<PieceReference>,
+    broken: bool,
+}
+
+/// Another piece named by a statement, with where the name is
+#[derive(Debug, Clone, PartialEq)]
+struct PieceReference {
+    name: String,
+    line: usize,
+    column: usize,
+}
+
+impl PieceReference {
+    fn new(token: &PieceToken, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            line: token.line,
+            column: token.column,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 enum PieceTokenKind {
     NameKeyword,
     ImageKeyword,
     LeapKeyword,
     KillKeyword,
     RunKeyword,
+    SlideKeyword,
+    HopKeyword,
+    CannonKillKeyword,
+    BetzaKeyword,
     SpecialKeyword,
     PromotionKeyword,
+    InheritsKeyword,
+    CombineKeyword,
+    RoyalKeyword,
+    CastlePartnerKeyword,
+    ValueKeyword,
 
     Colon,
+    Range,
+    Symmetry(Symmetry),
     Number(i32),
     Text(String),
+    /// A character or number the lexer can't make sense of, left for the parser to report
+    Unknown,
 
     EndOfFile,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct PieceToken {
     line: usize,
+    column: usize,
     text: String,
     kind: PieceTokenKind,
 }
@@ -48,82 +110,474 @@ enum PieceStatement {
         image_path: String,
     },
     Move {
-        kind: MoveRules,
-        forward: i32,
-        left: i32,
+        legs: Vec<MoveLeg>,
+    },
+    Betza {
+        piece_moves: Vec<PieceMove>,
     },
     SpecialMove {
         kind: MoveRules,
+        distance: Option<u32>,
     },
     Promotion {
-        piece_reference: String,
+        piece_reference: PieceReference,
+    },
+    Inherits {
+        piece_reference: PieceReference,
     },
+    Combine {
+        piece_references: Vec<PieceReference>,
+    },
+    Royal,
+    CastlePartner,
+    Value {
+        value: u32,
+    },
+}
+
+/// Shorthand after a move keyword that repeats the move in other directions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Symmetry {
+    /// `*`: every rotation and reflection
+    All,
+    /// `+`: the four quarter turns
+    Orthogonal,
+    /// `|`: the left/right mirror
+    Mirror,
+}
+
+impl Symmetry {
+    pub(crate) fn offsets(self, left: i32, forward: i32) -> Vec<(i32, i32)> {
+        match self {
+            Symmetry::All => vec![
+                (left, forward),
+                (-left, forward),
+                (left, -forward),
+                (-left, -forward),
+                (forward, left),
+                (-forward, left),
+                (forward, -left),
+                (-forward, -left),
+            ],
+            Symmetry::Orthogonal => vec![
+                (left, forward),
+                (-forward, left),
+                (-left, -forward),
+                (forward, -left),
+            ],
+            Symmetry::Mirror => vec![(left, forward), (-left, forward)],
+        }
+    }
+}
+
+/// One leg of a move statement, before its offset ranges are expanded
+#[derive(Debug)]
+struct MoveLeg {
+    kind: MoveRules,
+    symmetry: Option<Symmetry>,
+    forward: RangeInclusive<i32>,
+    left: RangeInclusive<i32>,
+    min_distance: u32,
+    max_distance: Option<u32>,
+}
+
+impl MoveLeg {
+    fn piece_moves(&self) -> Vec<PieceMove> {
+        let mut piece_moves = vec![];
+        for left in self.left.clone() {
+            for forward in self.forward.clone() {
+                // A range that crosses zero would otherwise give a move that stays put
+                if (left, forward) == (0, 0) {
+                    continue;
+                }
+                let offsets = match self.symmetry {
+                    Some(symmetry) => symmetry.offsets(left, forward),
+                    None => vec![(left, forward)],
+                };
+                for (left, forward) in offsets {
+                    let mut piece_move = PieceMove::new(forward, left, self.kind);
+                    piece_move.min_distance = self.min_distance;
+                    piece_move.max_distance = self.max_distance;
+                    if !piece_moves.contains(&piece_move) {
+                        piece_moves.push(piece_move);
+                    }
+                }
+            }
+        }
+        piece_moves
+    }
 }
 
 impl PieceCatalog {
     pub fn new() -> Result<PieceCatalog, crate::Error> {
         Ok(PieceCatalog {
             catalog: HashMap::new(),
+            sources: HashMap::new(),
+            incomplete: HashSet::new(),
         })
     }
 
+    /// Loads every piece file in the directory, reporting all the problems in
+    /// them at once
     pub fn generate(&mut self, dir_path: String) -> Result<(), crate::Error> {
-        let dir = fs::read_dir(dir_path)?;
-        for path in dir {
+        let mut paths = vec![];
+        for path in fs::read_dir(dir_path)? {
             let file = path?;
             if file.file_type()?.is_file() && file.file_name().to_string_lossy().ends_with(".txt") {
-                let reader = BufReader::new(File::open(file.path())?);
-                let piece = Self::read_piece(reader)?;
-                self.catalog.insert(piece.name.clone(), piece);
+                paths.push(file.path());
             }
         }
-        Ok(())
+        paths.sort();
+        let mut diagnostics = vec![];
+        for path in paths {
+            let reader = BufReader::new(File::open(&path)?);
+            diagnostics.extend(self.add_file(reader, path)?);
+        }
+        self.check(diagnostics)
+    }
+
+    /// Reads the pieces in one file into the catalog, returning the problems
+    /// found on the way. Pieces that read despite problems elsewhere in the
+    /// file are still added.
+    fn add_file<R: BufRead>(
+        &mut self,
+        reader: R,
+        file: PathBuf,
+    ) -> Result<Vec<PieceDiagnostic>, crate::Error> {
+        let tokens = Self::lex_piece(reader)?;
+        let (pieces, mut diagnostics) = Self::parse_pieces(tokens.into_iter());
+        for diagnostic in &mut diagnostics {
+            diagnostic.file = Some(file.clone());
+        }
+        for parsed in pieces {
+            diagnostics.extend(self.add_piece(parsed, file.clone()));
+        }
+        Ok(diagnostics)
+    }
+
+    fn add_piece(&mut self, parsed: ParsedPiece, file: PathBuf) -> Option<PieceDiagnostic> {
+        let source = PieceSource {
+            file,
+            line: parsed.line,
+            column: parsed.column,
+            references: parsed.references,
+            broken: parsed.broken,
+        };
+        let name = parsed.piece.name.clone();
+        if name.is_empty() {
+            // A piece that broke off before its Name has been reported already
+            if source.broken {
+                return None;
+            }
+            return Some(source.diagnostic("a Name statement", "a piece with no name"));
+        }
+        if let Some(other) = self.sources.get(&name) {
+            let found = format!("'{}', already in {}", name, other.file.display());
+            return Some(source.diagnostic("a piece name that isn't taken", &found));
+        }
+        self.sources.insert(name.clone(), source);
+        self.catalog.insert(name, parsed.piece);
+        None
+    }
+
+    /// Resolves and validates the pieces that loaded, adding what that finds to
+    /// the problems from loading them
+    fn check(&mut self, mut diagnostics: Vec<PieceDiagnostic>) -> Result<(), crate::Error> {
+        diagnostics.extend(self.resolve());
+        diagnostics.extend(self.validate());
+        diagnostics.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(PieceCatalogError { diagnostics }.into())
+        }
+    }
+
+    /// Resolves `Inherits` and `Combine` once every piece is loaded, leaving out
+    /// bases that are missing or would close a cycle
+    fn resolve(&mut self) -> Vec<PieceDiagnostic> {
+        let mut names = self.catalog.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        let mut resolved = HashSet::new();
+        let mut diagnostics = vec![];
+        for name in &names {
+            self.resolve_piece(name, &mut vec![], &mut resolved, &mut diagnostics);
+        }
+        diagnostics
+    }
+
+    /// Every piece needs an image and a move, and may only promote to real
+    /// pieces. A piece with bases left out has been reported already, so it
+    /// isn't blamed again for the moves those bases would have given it.
+    fn validate(&self) -> Vec<PieceDiagnostic> {
+        let mut names = self.catalog.keys().collect::<Vec<_>>();
+        names.sort_by_key(|name| {
+            let source = self.sources.get(*name);
+            (source.map(|s| (s.file.clone(), s.line, s.column)), *name)
+        });
+        let mut diagnostics = vec![];
+        for name in names {
+            let piece = &self.catalog[name];
+            let source = match self.sources.get(name) {
+                Some(source) if !source.broken => source,
+                _ => continue,
+            };
+            let none_for = format!("none for '{}'", name);
+            if piece.image_key.is_empty() {
+                diagnostics.push(source.diagnostic("an Image statement", &none_for));
+            }
+            if piece.move_set.is_empty() && !self.incomplete.contains(name) {
+                diagnostics.push(source.diagnostic("at least one move", &none_for));
+            }
+            for promotion in &piece.promotions {
+                if self.find_piece(promotion).is_none() {
+                    let found = format!("'{}' among the promotions of '{}'", promotion, name);
+                    diagnostics.push(source.reference_diagnostic(
+                        promotion,
+                        "a piece in the catalog",
+                        &found,
+                    ));
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Gives the piece the moves of its bases, resolving them first. Returns
+    /// false if the piece is already being resolved further up, so it would
+    /// build on itself.
+    fn resolve_piece(
+        &mut self,
+        name: &str,
+        stack: &mut Vec<String>,
+        resolved: &mut HashSet<String>,
+        diagnostics: &mut Vec<PieceDiagnostic>,
+    ) -> bool {
+        if resolved.contains(name) {
+            return true;
+        }
+        if stack.iter().any(|n| n == name) {
+            return false;
+        }
+        let piece = &self.catalog[name];
+        let inherits = piece.inherits.clone();
+        let references = inherits.iter().chain(&piece.combines).cloned();
+        let mut bases = vec![];
+        let mut parent = None;
+        let mut complete = true;
+        for (i, reference) in references.collect::<Vec<_>>().into_iter().enumerate() {
+            let base = match self.find_piece(&reference) {
+                Some(base) => base.name.clone(),
+                None => {
+                    let found = format!("'{}', which '{}' builds on", reference, name);
+                    diagnostics.push(self.reference_diagnostic(
+                        name,
+                        &reference,
+                        "a piece in the catalog",
+                        &found,
+                    ));
+                    complete = false;
+                    continue;
+                }
+            };
+            stack.push(name.to_string());
+            let resolves = self.resolve_piece(&base, stack, resolved, diagnostics);
+            stack.pop();
+            if !resolves {
+                let start = stack.iter().position(|n| *n == base).unwrap_or(stack.len());
+                let mut cycle = stack[start..].to_vec();
+                cycle.extend([name.to_string(), base.clone()]);
+                diagnostics.push(self.reference_diagnostic(
+                    name,
+                    &reference,
+                    "pieces that don't build on each other in a cycle",
+                    &cycle.join(" -> "),
+                ));
+                complete = false;
+                continue;
+            }
+            if self.incomplete.contains(&base) {
+                complete = false;
+            }
+            if i == 0 && inherits.is_some() {
+                parent = Some(base.clone());
+            }
+            bases.push(base);
+        }
+
+        let mut move_set = vec![];
+        for base in &bases {
+            for piece_move in &self.catalog[base].move_set {
+                if !move_set.contains(piece_move) {
+                    move_set.push(piece_move.clone());
+                }
+            }
+        }
+        let parent = parent.map(|parent| {
+            let parent = &self.catalog[&parent];
+            (
+                parent.image_key.clone(),
+                parent.promotions.clone(),
+                parent.royal,
+                parent.castle_partner,
+            )
+        });
+        let piece = self.catalog.get_mut(name).unwrap();
+        for piece_move in piece.move_set.drain(..) {
+            if !move_set.contains(&piece_move) {
+                move_set.push(piece_move);
+            }
+        }
+        piece.move_set = move_set;
+        // Whatever the piece sets itself wins over what it inherits
+        if let Some((image_key, promotions, royal, castle_partner)) = parent {
+            if piece.image_key.is_empty() {
+                piece.image_key = image_key;
+            }
+            if piece.promotions.is_empty() {
+                piece.promotions = promotions;
+            }
+            piece.royal |= royal;
+            piece.castle_partner |= castle_partner;
+        }
+        if !complete {
+            self.incomplete.insert(name.to_string());
+        }
+        resolved.insert(name.to_string());
+        true
+    }
+
+    /// A problem with a piece that `referenced_by` names, placed on the name
+    fn reference_diagnostic(
+        &self,
+        referenced_by: &str,
+        reference: &str,
+        expected: &str,
+        found: &str,
+    ) -> PieceDiagnostic {
+        match self.sources.get(referenced_by) {
+            Some(source) => source.reference_diagnostic(reference, expected, found),
+            None => PieceDiagnostic {
+                file: None,
+                line: 0,
+                column: 0,
+                expected: expected.to_string(),
+                found: found.to_string(),
+            },
+        }
     }
 
-    fn lex_piece<R: Read>(reader: R) -> Result<Vec<PieceToken>, crate::Error> {
-        let mut reader = reader.bytes().peekable();
+    fn lex_piece<R: BufRead>(reader: R) -> Result<Vec<PieceToken>, crate::Error> {
+        let consumed = Cell::new(0);
+        let mut reader = reader
+            .bytes()
+            .inspect(|_| consumed.set(consumed.get() + 1))
+            .peekable();
         let mut line = 1;
+        let mut line_start = 0;
         let mut tokens = Vec::<PieceToken>::new();
 
         while let Some(b) = reader.next() {
             let b = b?;
+            // Nothing has been peeked past `b` yet, so this is its place on the line
+            let column = consumed.get() - line_start;
 
             match b {
-                b'\n' => line += 1,
+                b'\n' => {
+                    line += 1;
+                    line_start = consumed.get();
+                }
                 b'\t' | b' ' | b'\r' => {}
+                b'#' => {
+                    // Comments run to the end of the line
+                    while let Some(Ok(b)) = reader.peek() {
+                        if *b == b'\n' {
+                            break;
+                        }
+                        reader.next();
+                    }
+                }
                 b':' => tokens.push(PieceToken {
                     line,
+                    column,
                     text: String::from(":"),
                     kind: PieceTokenKind::Colon,
                 }),
+                b'*' | b'+' | b'|' => tokens.push(PieceToken {
+                    line,
+                    column,
+                    text: (b as char).to_string(),
+                    kind: PieceTokenKind::Symmetry(match b {
+                        b'*' => Symmetry::All,
+                        b'+' => Symmetry::Orthogonal,
+                        _ => Symmetry::Mirror,
+                    }),
+                }),
+                b'.' => {
+                    let kind = match reader.peek() {
+                        Some(Ok(b'.')) => {
+                            reader.next();
+                            PieceTokenKind::Range
+                        }
+                        _ => PieceTokenKind::Unknown,
+                    };
+                    tokens.push(PieceToken {
+                        line,
+                        column,
+                        text: String::from(if kind == PieceTokenKind::Range {
+                            ".."
+                        } else {
+                            "."
+                        }),
+                        kind,
+                    })
+                }
                 b'-' => {
+                    let mut text = String::from("-");
                     while let Some(Ok(b'-')) = reader.peek() {
+                        text.push('-');
                         reader.next();
                     }
-                    if let Some(&Ok(b)) = reader.peek() {
-                        if b.is_ascii_digit() {
-                            reader.next();
-                            let text = format!("-{}", b as char);
-                            tokens.push(PieceToken {
-                                line,
-                                kind: PieceTokenKind::Number(text.parse()?),
-                                text,
-                            })
-                        }
+                    let dashes = text.len();
+                    while let Some(&Ok(b @ b'0'..=b'9')) = reader.peek() {
+                        text.push(b as char);
+                        reader.next();
+                    }
+                    // Two or more dashes on their own are a separator; anything
+                    // else but a minus sign on a number doesn't belong
+                    if dashes == 1 || text.len() > dashes {
+                        tokens.push(PieceToken {
+                            line,
+                            column,
+                            kind: match dashes {
+                                1 => text
+                                    .parse()
+                                    .map_or(PieceTokenKind::Unknown, PieceTokenKind::Number),
+                                _ => PieceTokenKind::Unknown,
+                            },
+                            text,
+                        })
                     }
                 }
                 b if b.is_ascii_digit() => {
-                    let text = (b as char).to_string();
+                    let mut text = (b as char).to_string();
+                    while let Some(&Ok(b @ b'0'..=b'9')) = reader.peek() {
+                        text.push(b as char);
+                        reader.next();
+                    }
                     tokens.push(PieceToken {
                         line,
-                        kind: PieceTokenKind::Number(text.parse()?),
+                        column,
+                        kind: text
+                            .parse()
+                            .map_or(PieceTokenKind::Unknown, PieceTokenKind::Number),
                         text,
                     });
                 }
                 b if b.is_ascii_alphabetic() => {
                     let mut word = (b as char).to_string();
                     while let Some(&Ok(mut b)) = reader.peek() {
-                        if b.is_ascii_alphabetic() || b == b'.' || b == b'_' {
+                        if b.is_ascii_alphanumeric() || b == b'.' || b == b'_' {
                             if b == b'_' {
                                 b = b' ';
                             }
@@ -137,207 +591,634 @@ impl PieceCatalog {
                         "Name" => PieceTokenKind::NameKeyword,
                         "Image" => PieceTokenKind::ImageKeyword,
                         "Run" => PieceTokenKind::RunKeyword,
+                        "Slide" => PieceTokenKind::SlideKeyword,
                         "Leap" => PieceTokenKind::LeapKeyword,
                         "Kill" => PieceTokenKind::KillKeyword,
+                        "Hop" => PieceTokenKind::HopKeyword,
+                        "CannonKill" => PieceTokenKind::CannonKillKeyword,
+                        "Betza" => PieceTokenKind::BetzaKeyword,
                         "Special" => PieceTokenKind::SpecialKeyword,
                         "Promotion" => PieceTokenKind::PromotionKeyword,
+                        "Inherits" => PieceTokenKind::InheritsKeyword,
+                        "Combine" => PieceTokenKind::CombineKeyword,
+                        "Royal" => PieceTokenKind::RoyalKeyword,
+                        "CastlePartner" => PieceTokenKind::CastlePartnerKeyword,
+                        "Value" => PieceTokenKind::ValueKeyword,
                         _ => PieceTokenKind::Text(word.clone()),
                     };
                     tokens.push(PieceToken {
                         line,
+                        column,
                         kind,
                         text: word,
                     })
                 }
-                _ => return Err(InvalidFormatError::new(line, (b as char).to_string()).into()),
+                _ => tokens.push(PieceToken {
+                    line,
+                    column,
+                    text: (b as char).to_string(),
+                    kind: PieceTokenKind::Unknown,
+                }),
             }
         }
         tokens.push(PieceToken {
             kind: PieceTokenKind::EndOfFile,
             line,
+            column: consumed.get() - line_start + 1,
             text: String::new(),
         });
         Ok(tokens)
     }
 
-    fn piece_name_statement(
+    /// The next token, which stays at the end of the file once it gets there
+    fn next_token(tokens: &mut Peekable<impl Iterator<Item = PieceToken>>) -> PieceToken {
+        if tokens.peek().unwrap().kind == PieceTokenKind::EndOfFile {
+            return tokens.peek().unwrap().clone();
+        }
+        tokens.next().unwrap()
+    }
+
+    fn expected(token: &PieceToken, expected: &str) -> PieceDiagnostic {
+        PieceDiagnostic {
+            file: None,
+            line: token.line,
+            column: token.column,
+            expected: expected.to_string(),
+            found: match token.kind {
+                PieceTokenKind::EndOfFile => String::from("the end of the file"),
+                _ => format!("'{}'", token.text),
+            },
+        }
+    }
+
+    fn colon(
         tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
-    ) -> Result<PieceStatement, crate::Error> {
-        tokens.next();
-        let colon = tokens.next().unwrap();
+    ) -> Result<(), PieceDiagnostic> {
+        let colon = Self::next_token(tokens);
         if colon.kind != PieceTokenKind::Colon {
-            return Err(InvalidFormatError::new(colon.line, colon.text).into());
+            return Err(Self::expected(&colon, "':'"));
         }
-        let name = tokens.next().unwrap();
+        Ok(())
+    }
+
+    fn piece_name_statement(
+        tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
+    ) -> Result<PieceStatement, PieceDiagnostic> {
+        tokens.next();
+        Self::colon(tokens)?;
+        let name = Self::next_token(tokens);
         match name.kind {
             PieceTokenKind::Text(name) => Ok(PieceStatement::Name { name }),
-            _ => Err(InvalidFormatError::new(name.line, name.text).into()),
+            _ => Err(Self::expected(&name, "a piece name")),
         }
     }
 
     fn piece_image_statement(
         tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
-    ) -> Result<PieceStatement, crate::Error> {
+    ) -> Result<PieceStatement, PieceDiagnostic> {
         tokens.next();
-        let colon = tokens.next().unwrap();
-        if colon.kind != PieceTokenKind::Colon {
-            return Err(InvalidFormatError::new(colon.line, colon.text).into());
-        }
-        let image = tokens.next().unwrap();
+        Self::colon(tokens)?;
+        let image = Self::next_token(tokens);
         match image.kind {
             PieceTokenKind::Text(image_path) => Ok(PieceStatement::Image { image_path }),
-            _ => Err(InvalidFormatError::new(image.line, image.text).into()),
+            _ => Err(Self::expected(&image, "an image file name")),
         }
     }
 
-    fn piece_move_statement(
+    /// An offset is a single number or an inclusive range such as `2..12`
+    fn piece_offset(
+        tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
+    ) -> Result<RangeInclusive<i32>, PieceDiagnostic> {
+        let start_token = Self::next_token(tokens);
+        let start = match start_token.kind {
+            PieceTokenKind::Number(n) => n,
+            _ => return Err(Self::expected(&start_token, "a number")),
+        };
+        if tokens.peek().unwrap().kind != PieceTokenKind::Range {
+            return Ok(start..=start);
+        }
+        tokens.next();
+        let end_token = Self::next_token(tokens);
+        match end_token.kind {
+            PieceTokenKind::Number(end) if end >= start => Ok(start..=end),
+            _ => Err(Self::expected(
+                &end_token,
+                &format!("a number no smaller than {}", start),
+            )),
+        }
+    }
+
+    fn piece_move_leg(
         tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
-    ) -> Result<PieceStatement, crate::Error> {
-        let move_token = tokens.next().unwrap();
+    ) -> Result<MoveLeg, PieceDiagnostic> {
+        let move_token = Self::next_token(tokens);
         let move_kind = match move_token.kind {
             PieceTokenKind::KillKeyword => MoveRules::Kill,
             PieceTokenKind::LeapKeyword => MoveRules::Leap,
             PieceTokenKind::RunKeyword => MoveRules::Run,
+            PieceTokenKind::SlideKeyword => MoveRules::Slide,
+            PieceTokenKind::HopKeyword => MoveRules::Hop,
+            PieceTokenKind::CannonKillKeyword => MoveRules::CannonKill,
             _ => unreachable!(),
         };
-        let colon = tokens.next().unwrap();
-        if colon.kind != PieceTokenKind::Colon {
-            return Err(InvalidFormatError::new(colon.line, colon.text).into());
+        // `Leap*: 2 1` repeats the move in every direction
+        let mut symmetry = None;
+        if let PieceTokenKind::Symmetry(s) = tokens.peek().unwrap().kind {
+            symmetry = Some(s);
+            tokens.next();
         }
-        let forward_token = tokens.next().unwrap();
-        let forward = match forward_token.kind {
-            PieceTokenKind::Number(f) => f,
-            _ => {
-                return Err(InvalidFormatError::new(forward_token.line, forward_token.text).into())
+        Self::colon(tokens)?;
+        // The sideways offset comes first, as in `Kill: 1 1` for a pawn capture
+        let left = Self::piece_offset(tokens)?;
+        let forward = Self::piece_offset(tokens)?;
+        // Runs and slides may limit how far they go, as in `Run: 1 0 max 3` or `Slide: 1 0 min 2`
+        let mut min_distance = 1;
+        let mut max_distance = None;
+        while let PieceTokenKind::Text(limit) = &tokens.peek().unwrap().kind {
+            let is_min = match limit.as_str() {
+                "min" => true,
+                "max" => false,
+                _ => break,
+            };
+            let limit_token = Self::next_token(tokens);
+            if !matches!(move_kind, MoveRules::Run | MoveRules::Slide) {
+                return Err(Self::expected(
+                    &limit_token,
+                    "min or max only after Run or Slide",
+                ));
             }
-        };
-        let left_token = tokens.next().unwrap();
-        let left = match left_token.kind {
-            PieceTokenKind::Number(l) => l,
-            _ => return Err(InvalidFormatError::new(left_token.line, left_token.text).into()),
-        };
-        Ok(PieceStatement::Move {
+            let distance_token = Self::next_token(tokens);
+            let distance = match distance_token.kind {
+                PieceTokenKind::Number(d) if d > 0 => d as u32,
+                _ => return Err(Self::expected(&distance_token, "a distance above zero")),
+            };
+            if is_min {
+                min_distance = distance;
+            } else {
+                max_distance = Some(distance);
+            }
+            if max_distance.is_some_and(|max| max < min_distance) {
+                return Err(Self::expected(
+                    &distance_token,
+                    "a max no smaller than the min",
+                ));
+            }
+        }
+        Ok(MoveLeg {
             kind: move_kind,
+            symmetry,
             forward,
             left,
+            min_distance,
+            max_distance,
         })
     }
 
+    fn piece_move_statement(
+        tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
+    ) -> Result<PieceStatement, PieceDiagnostic> {
+        let mut legs = vec![Self::piece_move_leg(tokens)?];
+        // Legs chain with `then`, as in `Leap: 1 1 then Run: 0 1`; every leg but
+        // the last only moves, so it has to be a Leap, a Run or a Slide
+        while let PieceTokenKind::Text(then) = &tokens.peek().unwrap().kind {
+            if then != "then" {
+                break;
+            }
+            let then_token = Self::next_token(tokens);
+            let next_is_move = matches!(
+                tokens.peek().unwrap().kind,
+                PieceTokenKind::LeapKeyword
+                    | PieceTokenKind::KillKeyword
+                    | PieceTokenKind::RunKeyword
+                    | PieceTokenKind::SlideKeyword
+                    | PieceTokenKind::HopKeyword
+                    | PieceTokenKind::CannonKillKeyword
+            );
+            let previous = legs.last().unwrap().kind;
+            let moves_only = matches!(
+                previous,
+                MoveRules::Leap | MoveRules::Run | MoveRules::Slide
+            );
+            if !next_is_move || !moves_only {
+                return Err(Self::expected(
+                    &then_token,
+                    "then between a Leap, Run or Slide and another move",
+                ));
+            }
+            legs.push(Self::piece_move_leg(tokens)?);
+        }
+        Ok(PieceStatement::Move { legs })
+    }
+
+    fn piece_betza_statement(
+        tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
+    ) -> Result<PieceStatement, PieceDiagnostic> {
+        tokens.next();
+        Self::colon(tokens)?;
+        let notation = Self::next_token(tokens);
+        let text = match &notation.kind {
+            PieceTokenKind::Text(text) => text,
+            _ => return Err(Self::expected(&notation, "Betza notation")),
+        };
+        let reason = match betza::piece_moves(text) {
+            Ok(piece_moves) => return Ok(PieceStatement::Betza { piece_moves }),
+            Err(betza::BetzaError::Unreadable { reason, .. }) => reason,
+            Err(error) => error.to_string(),
+        };
+        let mut diagnostic = Self::expected(&notation, "Betza notation");
+        diagnostic.found = format!("{} ({})", diagnostic.found, reason);
+        Err(diagnostic)
+    }
+
     fn piece_special_move_statement(
         tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
-    ) -> Result<PieceStatement, crate::Error> {
+    ) -> Result<PieceStatement, PieceDiagnostic> {
         tokens.next();
-        let colon = tokens.next().unwrap();
-        if colon.kind != PieceTokenKind::Colon {
-            return Err(InvalidFormatError::new(colon.line, colon.text).into());
-        }
-        let special_token = tokens.next().unwrap();
-        let special = match special_token.kind {
-            PieceTokenKind::Text(special) => special,
-            _ => {
-                return Err(InvalidFormatError::new(special_token.line, special_token.text).into())
-            }
+        Self::colon(tokens)?;
+        let special_token = Self::next_token(tokens);
+        let kind = match &special_token.kind {
+            PieceTokenKind::Text(special) if special == "Castle" => MoveRules::Castle,
+            PieceTokenKind::Text(special) if special == "PawnFirst" => MoveRules::PawnFirst,
+            _ => return Err(Self::expected(&special_token, "Castle or PawnFirst")),
         };
-        Ok(PieceStatement::SpecialMove {
-            kind: match special.as_str() {
-                "Castle" => MoveRules::Castle,
-                "PawnFirst" => MoveRules::PawnFirst,
-                _ => {
-                    return Err(
-                        InvalidFormatError::new(special_token.line, special_token.text).into(),
-                    )
+        // PawnFirst may say how many steps the first move reaches, as in `PawnFirst 3`
+        let mut distance = None;
+        if kind == MoveRules::PawnFirst {
+            if let PieceTokenKind::Number(d) = tokens.peek().unwrap().kind {
+                let distance_token = Self::next_token(tokens);
+                if d < 2 {
+                    return Err(Self::expected(
+                        &distance_token,
+                        "a first move distance of 2 or more",
+                    ));
                 }
-            },
-        })
+                distance = Some(d as u32);
+            }
+        }
+        Ok(PieceStatement::SpecialMove { kind, distance })
     }
 
     fn piece_promotion_statement(
         tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
-    ) -> Result<PieceStatement, crate::Error> {
+    ) -> Result<PieceStatement, PieceDiagnostic> {
         tokens.next();
-        let colon = tokens.next().unwrap();
-        if colon.kind != PieceTokenKind::Colon {
-            return Err(InvalidFormatError::new(colon.line, colon.text).into());
-        }
-        let promotion_piece_reference = tokens.next().unwrap();
-        let promotion = match promotion_piece_reference.kind {
+        Self::colon(tokens)?;
+        let promotion_piece_reference = Self::next_token(tokens);
+        let promotion = match &promotion_piece_reference.kind {
             PieceTokenKind::Text(promotion) => promotion,
-            _ => {
-                return Err(InvalidFormatError::new(
-                    promotion_piece_reference.line,
-                    promotion_piece_reference.text,
-                )
-                .into())
-            }
+            _ => return Err(Self::expected(&promotion_piece_reference, "a piece name")),
         };
         Ok(PieceStatement::Promotion {
-            piece_reference: promotion,
+            piece_reference: PieceReference::new(&promotion_piece_reference, promotion),
         })
     }
 
+    fn piece_inherits_statement(
+        tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
+    ) -> Result<PieceStatement, PieceDiagnostic> {
+        tokens.next();
+        Self::colon(tokens)?;
+        let parent = Self::next_token(tokens);
+        match &parent.kind {
+            PieceTokenKind::Text(name) => Ok(PieceStatement::Inherits {
+                piece_reference: PieceReference::new(&parent, name),
+            }),
+            _ => Err(Self::expected(&parent, "a piece name")),
+        }
+    }
+
+    fn piece_combine_statement(
+        tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
+    ) -> Result<PieceStatement, PieceDiagnostic> {
+        tokens.next();
+        Self::colon(tokens)?;
+        // Combine: Bishop Knight
+        let mut piece_references = vec![];
+        while let PieceTokenKind::Text(name) = &tokens.peek().unwrap().kind {
+            let name = name.clone();
+            let token = Self::next_token(tokens);
+            piece_references.push(PieceReference::new(&token, &name));
+        }
+        if piece_references.is_empty() {
+            let token = Self::next_token(tokens);
+            return Err(Self::expected(&token, "a piece name"));
+        }
+        Ok(PieceStatement::Combine { piece_references })
+    }
+
+    fn piece_value_statement(
+        tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
+    ) -> Result<PieceStatement, PieceDiagnostic> {
+        tokens.next();
+        Self::colon(tokens)?;
+        let value = Self::next_token(tokens);
+        match value.kind {
+            PieceTokenKind::Number(n) if n >= 0 => Ok(PieceStatement::Value { value: n as u32 }),
+            _ => Err(Self::expected(&value, "a value of zero or more")),
+        }
+    }
+
     fn piece_statement(
         tokens: &mut Peekable<impl Iterator<Item = PieceToken>>,
-    ) -> Result<PieceStatement, crate::Error> {
+    ) -> Result<PieceStatement, PieceDiagnostic> {
         match tokens.peek().unwrap().kind {
             PieceTokenKind::NameKeyword => Self::piece_name_statement(tokens),
             PieceTokenKind::ImageKeyword => Self::piece_image_statement(tokens),
             PieceTokenKind::LeapKeyword
             | PieceTokenKind::KillKeyword
-            | PieceTokenKind::RunKeyword => Self::piece_move_statement(tokens),
+            | PieceTokenKind::RunKeyword
+            | PieceTokenKind::SlideKeyword
+            | PieceTokenKind::HopKeyword
+            | PieceTokenKind::CannonKillKeyword => Self::piece_move_statement(tokens),
+            PieceTokenKind::BetzaKeyword => Self::piece_betza_statement(tokens),
             PieceTokenKind::SpecialKeyword => Self::piece_special_move_statement(tokens),
             PieceTokenKind::PromotionKeyword => Self::piece_promotion_statement(tokens),
-            _ => Err(InvalidFormatError::new(
-                tokens.peek().unwrap().line,
-                tokens.peek().unwrap().text.clone(),
-            )
-            .into()),
+            PieceTokenKind::InheritsKeyword => Self::piece_inherits_statement(tokens),
+            PieceTokenKind::CombineKeyword => Self::piece_combine_statement(tokens),
+            PieceTokenKind::RoyalKeyword => {
+                tokens.next();
+                Ok(PieceStatement::Royal)
+            }
+            PieceTokenKind::CastlePartnerKeyword => {
+                tokens.next();
+                Ok(PieceStatement::CastlePartner)
+            }
+            PieceTokenKind::ValueKeyword => Self::piece_value_statement(tokens),
+            _ => Err(Self::expected(
+                tokens.peek().unwrap(),
+                "a statement keyword",
+            )),
         }
     }
 
-    fn parse_piece(tokens: impl Iterator<Item = PieceToken>) -> Result<Piece, crate::Error> {
+    /// Reads the pieces in a file along with every problem in it. A `Name:` starts
+    /// a new piece once the current one has a name, and a bad statement is
+    /// skipped up to the next keyword, marking its piece as broken.
+    fn parse_pieces(
+        tokens: impl Iterator<Item = PieceToken>,
+    ) -> (Vec<ParsedPiece>, Vec<PieceDiagnostic>) {
         let mut tokens = tokens.peekable();
-        let mut statements = Vec::<PieceStatement>::new();
+        let mut statements = vec![];
+        let mut diagnostics = vec![];
         while tokens.peek().unwrap().kind != PieceTokenKind::EndOfFile {
-            statements.push(Self::piece_statement(&mut tokens)?);
+            let start = tokens.peek().unwrap().clone();
+            match Self::piece_statement(&mut tokens) {
+                Ok(statement) => statements.push((start, Some(statement))),
+                Err(diagnostic) => {
+                    diagnostics.push(diagnostic);
+                    while !Self::starts_statement(&tokens.peek().unwrap().kind) {
+                        tokens.next();
+                    }
+                    statements.push((start, None));
+                }
+            }
         }
-        let mut piece = Piece::new();
-        dbg!(&statements);
-        for statement in statements {
+        let mut pieces = vec![];
+        let mut piece: Option<ParsedPiece> = None;
+        for (start, statement) in statements {
+            // A Name starts the next piece once the current one has its own, so
+            // statements written before a piece's Name still belong to it
+            let named = piece
+                .as_ref()
+                .is_some_and(|parsed| !parsed.piece.name.is_empty());
+            if start.kind == PieceTokenKind::NameKeyword && named {
+                pieces.extend(piece.take());
+            }
+            let parsed = piece.get_or_insert_with(|| ParsedPiece {
+                piece: Piece::new(),
+                line: start.line,
+                column: start.column,
+                references: vec![],
+                broken: false,
+            });
             match statement {
-                PieceStatement::Name { name } => piece.name = name,
-                PieceStatement::Image { image_path } => piece.image_key = image_path,
-                PieceStatement::Move {
-                    kind,
-                    forward,
-                    left,
-                } => piece.move_set.push(PieceMove::new(forward, left, kind)),
-                PieceStatement::SpecialMove { kind } => {
-                    piece.move_set.push(PieceMove::new_special(kind))
+                Some(statement) => Self::apply_statement(parsed, statement),
+                None => parsed.broken = true,
+            }
+        }
+        pieces.extend(piece);
+        (pieces, diagnostics)
+    }
+
+    fn starts_statement(kind: &PieceTokenKind) -> bool {
+        matches!(
+            kind,
+            PieceTokenKind::NameKeyword
+                | PieceTokenKind::ImageKeyword
+                | PieceTokenKind::LeapKeyword
+                | PieceTokenKind::KillKeyword
+                | PieceTokenKind::RunKeyword
+                | PieceTokenKind::SlideKeyword
+                | PieceTokenKind::HopKeyword
+                | PieceTokenKind::CannonKillKeyword
+                | PieceTokenKind::BetzaKeyword
+                | PieceTokenKind::SpecialKeyword
+                | PieceTokenKind::PromotionKeyword
+                | PieceTokenKind::InheritsKeyword
+                | PieceTokenKind::CombineKeyword
+                | PieceTokenKind::RoyalKeyword
+                | PieceTokenKind::CastlePartnerKeyword
+                | PieceTokenKind::ValueKeyword
+                | PieceTokenKind::EndOfFile
+        )
+    }
+
+    fn apply_statement(parsed: &mut ParsedPiece, statement: PieceStatement) {
+        let (piece, references) = (&mut parsed.piece, &mut parsed.references);
+        match statement {
+            PieceStatement::Name { name } => piece.name = name,
+            PieceStatement::Image { image_path } => piece.image_key = image_path,
+            PieceStatement::Move { legs } => {
+                // Every combination of the legs' expanded offsets is its own move
+                let mut chains = vec![vec![]];
+                for leg in &legs {
+                    let leg_moves = leg.piece_moves();
+                    chains = chains
+                        .into_iter()
+                        .flat_map(|chain: Vec<PieceMove>| {
+                            leg_moves.iter().map(move |leg_move| {
+                                let mut chain = chain.clone();
+                                chain.push(leg_move.clone());
+                                chain
+                            })
+                        })
+                        .collect();
+                }
+                for chain in chains {
+                    let mut chain = chain.into_iter();
+                    let mut piece_move = chain.next().unwrap();
+                    piece_move.legs = chain.collect();
+                    if !piece.move_set.contains(&piece_move) {
+                        piece.move_set.push(piece_move);
+                    }
+                }
+            }
+            PieceStatement::Betza { piece_moves } => {
+                for piece_move in piece_moves {
+                    if !piece.move_set.contains(&piece_move) {
+                        piece.move_set.push(piece_move);
+                    }
                 }
-                PieceStatement::Promotion { piece_reference } => {
-                    piece.promotions.push(piece_reference)
+            }
+            PieceStatement::SpecialMove { kind, distance } => {
+                let mut special = PieceMove::new_special(kind);
+                if kind == MoveRules::PawnFirst {
+                    special.max_distance = Some(distance.unwrap_or(DEFAULT_PAWN_FIRST_DISTANCE));
                 }
+                piece.move_set.push(special);
+            }
+            PieceStatement::Promotion { piece_reference } => {
+                piece.promotions.push(piece_reference.name.clone());
+                references.push(piece_reference);
+            }
+            PieceStatement::Inherits { piece_reference } => {
+                piece.inherits = Some(piece_reference.name.clone());
+                references.push(piece_reference);
             }
+            PieceStatement::Combine { piece_references } => {
+                piece.combines.extend(
+                    piece_references
+                        .iter()
+                        .map(|reference| reference.name.clone()),
+                );
+                references.extend(piece_references);
+            }
+            PieceStatement::Royal => piece.royal = true,
+            PieceStatement::CastlePartner => piece.castle_partner = true,
+            PieceStatement::Value { value } => piece.value = Some(value),
         }
-        Ok(piece)
     }
 
-    fn read_piece<R: Read>(reader: R) -> Result<Piece, crate::Error> {
+    /// Reads the pieces in a file, failing on any problem in it
+    #[cfg(test)]
+    fn read_pieces<R: BufRead>(reader: R) -> Result<Vec<ParsedPiece>, crate::Error> {
         let tokens = Self::lex_piece(reader)?;
-        Self::parse_piece(tokens.into_iter())
+        let (pieces, diagnostics) = Self::parse_pieces(tokens.into_iter());
+        if !diagnostics.is_empty() {
+            return Err(PieceCatalogError { diagnostics }.into());
+        }
+        Ok(pieces)
+    }
+
+    /// Writes a piece in the piece file format, so that reading it back gives
+    /// the same piece. Moves keep their order, with a separator wherever the
+    /// kind of move changes.
+    pub fn write_piece(piece: &Piece) -> String {
+        let mut header = vec![format!("Name: {}", file_word(&piece.name))];
+        if !piece.image_key.is_empty() {
+            header.push(format!("Image: {}", file_word(&piece.image_key)));
+        }
+        if let Some(parent) = &piece.inherits {
+            header.push(format!("Inherits: {}", file_word(parent)));
+        }
+        if !piece.combines.is_empty() {
+            let names = piece.combines.iter().map(|name| file_word(name));
+            header.push(format!("Combine: {}", names.collect::<Vec<_>>().join(" ")));
+        }
+        if piece.royal {
+            header.push(String::from("Royal"));
+        }
+        if piece.castle_partner {
+            header.push(String::from("CastlePartner"));
+        }
+        if let Some(value) = piece.value {
+            header.push(format!("Value: {}", value));
+        }
+
+        let mut sections = vec![header];
+        let mut previous = None;
+        for piece_move in &piece.move_set {
+            if previous != Some(piece_move.rules) {
+                sections.push(vec![]);
+                previous = Some(piece_move.rules);
+            }
+            sections.last_mut().unwrap().push(write_move(piece_move));
+        }
+        if !piece.promotions.is_empty() {
+            let promotions = piece.promotions.iter();
+            sections.push(
+                promotions
+                    .map(|name| format!("Promotion: {}", file_word(name)))
+                    .collect(),
+            );
+        }
+
+        let mut out = String::from(SEPARATOR);
+        for section in sections {
+            for line in section {
+                out.push('\n');
+                out.push_str(&line);
+            }
+            out.push('\n');
+            out.push_str(SEPARATOR);
+        }
+        out.push('\n');
+        out
     }
 
+    /// Looks a piece up by name, ignoring case if there is no exact match
     pub fn get_piece(&self, piece_name: &str) -> Result<&Piece, crate::Error> {
         let out = self
-            .catalog
-            .get(piece_name)
+            .find_piece(piece_name)
             .ok_or_else(|| PieceNotFoundError {
                 name: piece_name.to_string(),
             })?;
         Ok(out)
     }
+
+    fn find_piece(&self, piece_name: &str) -> Option<&Piece> {
+        self.catalog.get(piece_name).or_else(|| {
+            self.catalog
+                .values()
+                .find(|piece| piece.name.eq_ignore_ascii_case(piece_name))
+        })
+    }
+}
+
+/// The keyword a move statement of the kind starts with
+fn rule_keyword(rules: MoveRules) -> &'static str {
+    match rules {
+        MoveRules::Leap => "Leap",
+        MoveRules::Kill => "Kill",
+        MoveRules::Run => "Run",
+        MoveRules::Slide => "Slide",
+        MoveRules::Hop => "Hop",
+        MoveRules::CannonKill => "CannonKill",
+        MoveRules::PawnFirst | MoveRules::Castle => "Special",
+    }
+}
+
+/// One move statement, with any later legs chained on with `then`
+fn write_move(piece_move: &PieceMove) -> String {
+    match piece_move.rules {
+        MoveRules::PawnFirst => {
+            return match piece_move.max_distance {
+                Some(distance) if distance != DEFAULT_PAWN_FIRST_DISTANCE => {
+                    format!("Special: PawnFirst {}", distance)
+                }
+                _ => String::from("Special: PawnFirst"),
+            }
+        }
+        MoveRules::Castle => return String::from("Special: Castle"),
+        _ => {}
+    }
+    let legs = std::iter::once(piece_move).chain(&piece_move.legs);
+    let legs = legs.map(|leg| {
+        let mut text = format!("{}: {} {}", rule_keyword(leg.rules), leg.left, leg.forward);
+        if leg.min_distance != 1 {
+            text.push_str(&format!(" min {}", leg.min_distance));
+        }
+        if let Some(max) = leg.max_distance {
+            text.push_str(&format!(" max {}", max));
+        }
+        text
+    });
+    legs.collect::<Vec<_>>().join(" then ")
 }
 
+/// A lookup by name in a loaded catalog that found nothing. A missing
+/// `Inherits` or `Combine` base is found while loading instead, and is reported
+/// as a `PieceDiagnostic` that names the referencing file, line and column.
 #[derive(Debug)]
 pub struct PieceNotFoundError {
     name: String,
@@ -351,18 +1232,101 @@ impl Display for PieceNotFoundError {
 
 impl std::error::Error for PieceNotFoundError {}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use expect_test::{expect, Expect};
+impl PieceSource {
+    fn diagnostic(&self, expected: &str, found: &str) -> PieceDiagnostic {
+        PieceDiagnostic {
+            file: Some(self.file.clone()),
+            line: self.line,
+            column: self.column,
+            expected: expected.to_string(),
+            found: found.to_string(),
+        }
+    }
 
-    fn check(expected: Expect, actual: String) {
-        expected.assert_eq(&actual);
+    /// A problem placed where the piece names `reference`, or at the piece's
+    /// start if it doesn't name it itself
+    fn reference_diagnostic(
+        &self,
+        reference: &str,
+        expected: &str,
+        found: &str,
+    ) -> PieceDiagnostic {
+        let mut diagnostic = self.diagnostic(expected, found);
+        if let Some(place) = self.references.iter().find(|place| place.name == reference) {
+            diagnostic.line = place.line;
+            diagnostic.column = place.column;
+        }
+        diagnostic
     }
+}
 
-    #[test]
-    fn lex_stuff() {
-        let data = "
+/// One problem in a piece file: where it is, and what should have been there
+#[derive(Debug)]
+pub struct PieceDiagnostic {
+    file: Option<PathBuf>,
+    line: usize,
+    column: usize,
+    expected: String,
+    found: String,
+}
+
+impl Display for PieceDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+        write!(
+            f,
+            "{}:{}: expected {}, found {}",
+            self.line, self.column, self.expected, self.found
+        )
+    }
+}
+
+/// Every problem found while loading the piece catalog
+#[derive(Debug)]
+pub struct PieceCatalogError {
+    diagnostics: Vec<PieceDiagnostic>,
+}
+
+impl Display for PieceCatalogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl std::error::Error for PieceCatalogError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, Expect};
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    fn parse_piece(tokens: impl Iterator<Item = PieceToken>) -> Result<Piece, crate::Error> {
+        let (mut pieces, diagnostics) = PieceCatalog::parse_pieces(tokens);
+        if !diagnostics.is_empty() {
+            return Err(PieceCatalogError { diagnostics }.into());
+        }
+        assert_eq!(pieces.len(), 1);
+        Ok(pieces.remove(0).piece)
+    }
+
+    fn read_piece(data: &[u8]) -> Result<Piece, crate::Error> {
+        parse_piece(PieceCatalog::lex_piece(data)?.into_iter())
+    }
+
+    #[test]
+    fn lex_stuff() {
+        let data = "
             Name: King
             ";
         let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
@@ -371,16 +1335,19 @@ mod tests {
                 [
                     PieceToken {
                         line: 2,
+                        column: 13,
                         text: "Name",
                         kind: NameKeyword,
                     },
                     PieceToken {
                         line: 2,
+                        column: 17,
                         text: ":",
                         kind: Colon,
                     },
                     PieceToken {
                         line: 2,
+                        column: 19,
                         text: "King",
                         kind: Text(
                             "King",
@@ -388,6 +1355,7 @@ mod tests {
                     },
                     PieceToken {
                         line: 3,
+                        column: 13,
                         text: "",
                         kind: EndOfFile,
                     },
@@ -399,7 +1367,7 @@ mod tests {
     #[test]
     fn parse_name_statement() {
         let data = "Name: King";
-        let piece = PieceCatalog::parse_piece(
+        let piece = parse_piece(
             PieceCatalog::lex_piece(data.as_bytes())
                 .unwrap()
                 .into_iter(),
@@ -407,12 +1375,17 @@ mod tests {
         .unwrap();
         check(
             expect![[r#"
-            Piece {
-                name: "King",
-                image_key: "",
-                move_set: [],
-                promotions: [],
-            }"#]],
+                Piece {
+                    name: "King",
+                    image_key: "",
+                    move_set: [],
+                    promotions: [],
+                    royal: false,
+                    castle_partner: false,
+                    inherits: None,
+                    combines: [],
+                    value: None,
+                }"#]],
             format!("{:#?}", piece),
         );
     }
@@ -421,21 +1394,29 @@ mod tests {
     fn parse_move_statement() {
         let data = "Leap: -1 1";
         let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
-        let piece = PieceCatalog::parse_piece(tokens.into_iter()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
         check(
             expect![[r#"
-            Piece {
-                name: "",
-                image_key: "",
-                move_set: [
-                    PieceMove {
-                        forward: -1,
-                        left: 1,
-                        rules: Leap,
-                    },
-                ],
-                promotions: [],
-            }"#]],
+                Piece {
+                    name: "",
+                    image_key: "",
+                    move_set: [
+                        PieceMove {
+                            forward: 1,
+                            left: -1,
+                            rules: Leap,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
+                        },
+                    ],
+                    promotions: [],
+                    royal: false,
+                    castle_partner: false,
+                    inherits: None,
+                    combines: [],
+                    value: None,
+                }"#]],
             format!("{:#?}", piece),
         );
     }
@@ -468,7 +1449,7 @@ mod tests {
             Special: Castle
             ----------------------------
         ";
-        let piece = PieceCatalog::read_piece(data.as_bytes()).unwrap();
+        let piece = read_piece(data.as_bytes()).unwrap();
         check(
             expect![[r#"
                 Piece {
@@ -479,89 +1460,81 @@ mod tests {
                             forward: 1,
                             left: 1,
                             rules: Leap,
-                        },
-                        PieceMove {
-                            forward: 1,
-                            left: -1,
-                            rules: Leap,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
                         },
                         PieceMove {
                             forward: -1,
                             left: 1,
                             rules: Leap,
-                        },
-                        PieceMove {
-                            forward: -1,
-                            left: -1,
-                            rules: Leap,
-                        },
-                        PieceMove {
-                            forward: 1,
-                            left: 1,
-                            rules: Leap,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
                         },
                         PieceMove {
                             forward: 1,
                             left: -1,
                             rules: Leap,
-                        },
-                        PieceMove {
-                            forward: -1,
-                            left: 1,
-                            rules: Leap,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
                         },
                         PieceMove {
                             forward: -1,
                             left: -1,
                             rules: Leap,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
                         },
                         PieceMove {
                             forward: 1,
                             left: 1,
                             rules: Kill,
-                        },
-                        PieceMove {
-                            forward: 1,
-                            left: -1,
-                            rules: Kill,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
                         },
                         PieceMove {
                             forward: -1,
                             left: 1,
                             rules: Kill,
-                        },
-                        PieceMove {
-                            forward: -1,
-                            left: -1,
-                            rules: Kill,
-                        },
-                        PieceMove {
-                            forward: 1,
-                            left: 1,
-                            rules: Kill,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
                         },
                         PieceMove {
                             forward: 1,
                             left: -1,
                             rules: Kill,
-                        },
-                        PieceMove {
-                            forward: -1,
-                            left: 1,
-                            rules: Kill,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
                         },
                         PieceMove {
                             forward: -1,
                             left: -1,
                             rules: Kill,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
                         },
                         PieceMove {
                             forward: 0,
                             left: 0,
                             rules: Castle,
+                            min_distance: 1,
+                            max_distance: None,
+                            legs: [],
                         },
                     ],
                     promotions: [],
+                    royal: false,
+                    castle_partner: false,
+                    inherits: None,
+                    combines: [],
+                    value: None,
                 }"#]],
             format!("{:#?}", piece),
         );
@@ -571,15 +1544,20 @@ mod tests {
     fn name_with_space() {
         let data = "Name: King_Killer";
         let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
-        let piece = PieceCatalog::parse_piece(tokens.into_iter()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
         check(
             expect![[r#"
-            Piece {
-                name: "King Killer",
-                image_key: "",
-                move_set: [],
-                promotions: [],
-            }"#]],
+                Piece {
+                    name: "King Killer",
+                    image_key: "",
+                    move_set: [],
+                    promotions: [],
+                    royal: false,
+                    castle_partner: false,
+                    inherits: None,
+                    combines: [],
+                    value: None,
+                }"#]],
             format!("{:#?}", piece),
         );
     }
@@ -588,16 +1566,593 @@ mod tests {
     fn multiple_lines_in_one() {
         let data = "Name: King Image: King.png";
         let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
-        let piece = PieceCatalog::parse_piece(tokens.into_iter()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
+        check(
+            expect![[r#"
+                Piece {
+                    name: "King",
+                    image_key: "King.png",
+                    move_set: [],
+                    promotions: [],
+                    royal: false,
+                    castle_partner: false,
+                    inherits: None,
+                    combines: [],
+                    value: None,
+                }"#]],
+            format!("{:#?}", piece),
+        );
+    }
+
+    #[test]
+    fn parse_royal_statement() {
+        let data = "Name: King Royal";
+        let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
         check(
             expect![[r#"
-            Piece {
-                name: "King",
-                image_key: "King.png",
-                move_set: [],
-                promotions: [],
-            }"#]],
+                Piece {
+                    name: "King",
+                    image_key: "",
+                    move_set: [],
+                    promotions: [],
+                    royal: true,
+                    castle_partner: false,
+                    inherits: None,
+                    combines: [],
+                    value: None,
+                }"#]],
             format!("{:#?}", piece),
         );
     }
+
+    #[test]
+    fn parse_pawn_first_distance() {
+        let data = "Special: PawnFirst 3 Special: PawnFirst Special: Castle";
+        let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
+        check(
+            expect![[r#"
+                [
+                    PieceMove {
+                        forward: 0,
+                        left: 0,
+                        rules: PawnFirst,
+                        min_distance: 1,
+                        max_distance: Some(
+                            3,
+                        ),
+                        legs: [],
+                    },
+                    PieceMove {
+                        forward: 0,
+                        left: 0,
+                        rules: PawnFirst,
+                        min_distance: 1,
+                        max_distance: Some(
+                            2,
+                        ),
+                        legs: [],
+                    },
+                    PieceMove {
+                        forward: 0,
+                        left: 0,
+                        rules: Castle,
+                        min_distance: 1,
+                        max_distance: None,
+                        legs: [],
+                    },
+                ]"#]],
+            format!("{:#?}", piece.move_set),
+        );
+    }
+
+    #[test]
+    fn lex_long_and_signed_numbers() {
+        let data = "Leap: 10 3\nRun: -12 0\n----\nLeap: 0 2..-1";
+        let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
+        check(
+            expect![[
+                r#"[LeapKeyword, Colon, Number(10), Number(3), RunKeyword, Colon, Number(-12), Number(0), LeapKeyword, Colon, Number(0), Number(2), Range, Number(-1), EndOfFile]"#
+            ]],
+            format!(
+                "{:?}",
+                tokens.iter().map(|token| &token.kind).collect::<Vec<_>>()
+            ),
+        );
+    }
+
+    #[test]
+    fn parse_move_ranges() {
+        let data = "Leap: 10 3 Run: -12 0 Leap: 0 2..4 Kill: -1..1 1";
+        let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
+        check(
+            expect![[
+                r#"[(Leap, 10, 3), (Run, -12, 0), (Leap, 0, 2), (Leap, 0, 3), (Leap, 0, 4), (Kill, -1, 1), (Kill, 0, 1), (Kill, 1, 1)]"#
+            ]],
+            format!(
+                "{:?}",
+                piece
+                    .move_set
+                    .iter()
+                    .map(|m| (m.rules, m.left, m.forward))
+                    .collect::<Vec<_>>()
+            ),
+        );
+    }
+
+    #[test]
+    fn reports_bad_ranges() {
+        let error = |data: &str| read_piece(data.as_bytes()).unwrap_err().to_string();
+        check(
+            expect![[r#"1:10: expected a number no smaller than 3, found '1'"#]],
+            error("Leap: 3..1 0"),
+        );
+        check(
+            expect![[r#"1:8: expected a number, found '.'"#]],
+            error("Leap: 1.2 0"),
+        );
+        check(
+            expect![[r#"1:12: expected a number no smaller than 1, found the end of the file"#]],
+            error("Leap: 0 1.."),
+        );
+    }
+
+    #[test]
+    fn parse_run_limits() {
+        let data = "Run: 1 0 max 3 Run: 0 1 min 2 Run: 0 -1 min 2 max 2 Run: -1 0";
+        let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
+        check(
+            expect![[
+                r#"[(1, 0, 1, Some(3)), (0, 1, 2, None), (0, -1, 2, Some(2)), (-1, 0, 1, None)]"#
+            ]],
+            format!(
+                "{:?}",
+                piece
+                    .move_set
+                    .iter()
+                    .map(|m| (m.left, m.forward, m.min_distance, m.max_distance))
+                    .collect::<Vec<_>>()
+            ),
+        );
+    }
+
+    #[test]
+    fn reports_bad_run_limits() {
+        let error = |data: &str| read_piece(data.as_bytes()).unwrap_err().to_string();
+        check(
+            expect![[r#"1:11: expected min or max only after Run or Slide, found 'max'"#]],
+            error("Leap: 1 0 max 3"),
+        );
+        check(
+            expect![[r#"1:14: expected a distance above zero, found '0'"#]],
+            error("Run: 1 0 max 0"),
+        );
+        check(
+            expect![[r#"1:20: expected a max no smaller than the min, found '3'"#]],
+            error("Run: 1 0 min 4 max 3"),
+        );
+    }
+
+    #[test]
+    fn parse_value_overrides() {
+        let piece = read_piece(b"Name: Archbishop Value: 8 Leap: 1 2").unwrap();
+        assert_eq!(piece.value, Some(8));
+        check(
+            expect![[r#"1:8: expected a value of zero or more, found '-1'"#]],
+            read_piece(b"Value: -1").unwrap_err().to_string(),
+        );
+        check(
+            expect![[r#"1:8: expected a value of zero or more, found 'Leap'"#]],
+            read_piece(b"Value: Leap: 1 2").unwrap_err().to_string(),
+        );
+    }
+
+    #[test]
+    fn parse_hop_statements() {
+        let data = "Hop: 0 1 CannonKill: -1 0";
+        let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
+        check(
+            expect![[r#"[(Hop, 0, 1), (CannonKill, -1, 0)]"#]],
+            format!(
+                "{:?}",
+                piece
+                    .move_set
+                    .iter()
+                    .map(|m| (m.rules, m.left, m.forward))
+                    .collect::<Vec<_>>()
+            ),
+        );
+    }
+
+    #[test]
+    fn parse_move_legs() {
+        let data = "Leap: 1 1 then Run: 0 1 max 2 Leap: 0 1 then Leap: -1..1 1 then Kill: 0 1";
+        let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
+        check(
+            expect![[r#"
+                Leap 1 1 then Run 0 1
+                Leap 0 1 then Leap -1 1 then Kill 0 1
+                Leap 0 1 then Leap 0 1 then Kill 0 1
+                Leap 0 1 then Leap 1 1 then Kill 0 1"#]],
+            piece
+                .move_set
+                .iter()
+                .map(|m| {
+                    std::iter::once(m)
+                        .chain(&m.legs)
+                        .map(|leg| format!("{:?} {} {}", leg.rules, leg.left, leg.forward))
+                        .collect::<Vec<_>>()
+                        .join(" then ")
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+
+    #[test]
+    fn reports_bad_move_legs() {
+        let error = |data: &str| read_piece(data.as_bytes()).unwrap_err().to_string();
+        check(
+            expect![[
+                r#"1:11: expected then between a Leap, Run or Slide and another move, found 'then'"#
+            ]],
+            error("Kill: 1 1 then Run: 0 1"),
+        );
+        check(
+            expect![[
+                r#"1:11: expected then between a Leap, Run or Slide and another move, found 'then'"#
+            ]],
+            error("Leap: 1 1 then Royal"),
+        );
+    }
+
+    #[test]
+    fn parse_betza_statement() {
+        let data = "Name: Pawn2 Betza: fmWfcF Leap: 0 2";
+        let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
+        let piece = parse_piece(tokens.into_iter()).unwrap();
+        check(
+            expect![[r#"Pawn2 [(Leap, 0, 1), (Kill, -1, 1), (Kill, 1, 1), (Leap, 0, 2)]"#]],
+            format!(
+                "{} {:?}",
+                piece.name,
+                piece
+                    .move_set
+                    .iter()
+                    .map(|m| (m.rules, m.left, m.forward))
+                    .collect::<Vec<_>>()
+            ),
+        );
+        let error = read_piece("Betza: fmY".as_bytes()).unwrap_err();
+        check(
+            expect![[r#"1:8: expected Betza notation, found 'fmY' (there is no atom 'Y')"#]],
+            error.to_string(),
+        );
+    }
+
+    #[test]
+    fn parse_symmetry_shorthand() {
+        let moves = |data: &str| {
+            let tokens = PieceCatalog::lex_piece(data.as_bytes()).unwrap();
+            let piece = parse_piece(tokens.into_iter()).unwrap();
+            format!(
+                "{:?}",
+                piece
+                    .move_set
+                    .iter()
+                    .map(|m| (m.rules, m.left, m.forward))
+                    .collect::<Vec<_>>()
+            )
+        };
+        check(
+            expect![[
+                r#"[(Leap, 2, 1), (Leap, -2, 1), (Leap, 2, -1), (Leap, -2, -1), (Leap, 1, 2), (Leap, -1, 2), (Leap, 1, -2), (Leap, -1, -2)]"#
+            ]],
+            moves("Leap*: 2 1"),
+        );
+        check(
+            expect![[r#"[(Run, 1, 0), (Run, 0, 1), (Run, -1, 0), (Run, 0, -1)]"#]],
+            moves("Run+: 1 0"),
+        );
+        check(
+            expect![[r#"[(Kill, 1, 1), (Kill, -1, 1)]"#]],
+            moves("Kill|: 1 1"),
+        );
+        // Symmetric copies that land on the same offset, or repeat an earlier move, are dropped
+        check(
+            expect![[
+                r#"[(Leap, 1, 1), (Leap, -1, 1), (Leap, 1, -1), (Leap, -1, -1), (Kill, 0, 1)]"#
+            ]],
+            moves("Leap*: 1 1 Leap: 1 1 Kill|: 0 1"),
+        );
+    }
+
+    fn catalog_of(files: &[(&str, &str)]) -> Result<PieceCatalog, crate::Error> {
+        let mut catalog = PieceCatalog::new()?;
+        let mut diagnostics = vec![];
+        for (file, data) in files {
+            diagnostics.extend(catalog.add_file(data.as_bytes(), PathBuf::from(file))?);
+        }
+        catalog.check(diagnostics)?;
+        Ok(catalog)
+    }
+
+    #[test]
+    fn resolve_inherits_and_combine() {
+        let catalog = catalog_of(&[
+            (
+                "Rook.txt",
+                "Name: Rook Image: Rook.png CastlePartner Run+: 1 0",
+            ),
+            ("Bishop.txt", "Name: Bishop Image: Bishop.png Run+: 1 1"),
+            ("Knight.txt", "Name: Knight Image: Knight.png Leap*: 2 1"),
+            (
+                "Chancellor.txt",
+                "Name: Chancellor Inherits: Rook Combine: knight Leap: 0 1",
+            ),
+            (
+                "Archbishop.txt",
+                "Name: Archbishop Image: A.png Combine: Bishop Knight",
+            ),
+        ])
+        .unwrap();
+        let describe = |name: &str| {
+            let piece = catalog.get_piece(name).unwrap();
+            format!(
+                "{} {} {} {:?}",
+                piece.name,
+                piece.image_key,
+                piece.castle_partner,
+                piece
+                    .move_set
+                    .iter()
+                    .map(|m| (m.rules, m.left, m.forward))
+                    .collect::<Vec<_>>()
+            )
+        };
+        check(
+            expect![[
+                r#"Chancellor Rook.png true [(Run, 1, 0), (Run, 0, 1), (Run, -1, 0), (Run, 0, -1), (Leap, 2, 1), (Leap, -2, 1), (Leap, 2, -1), (Leap, -2, -1), (Leap, 1, 2), (Leap, -1, 2), (Leap, 1, -2), (Leap, -1, -2), (Leap, 0, 1)]"#
+            ]],
+            describe("Chancellor"),
+        );
+        check(
+            expect![[
+                r#"Archbishop A.png false [(Run, 1, 1), (Run, -1, 1), (Run, -1, -1), (Run, 1, -1), (Leap, 2, 1), (Leap, -2, 1), (Leap, 2, -1), (Leap, -2, -1), (Leap, 1, 2), (Leap, -1, 2), (Leap, 1, -2), (Leap, -1, -2)]"#
+            ]],
+            describe("Archbishop"),
+        );
+    }
+
+    #[test]
+    fn reports_missing_bases_and_cycles() {
+        let error = |files: &[(&str, &str)]| catalog_of(files).unwrap_err().to_string();
+        check(
+            expect![[r#"
+                Amazon.txt:1:36: expected a piece in the catalog, found 'Queen', which 'Amazon' builds on
+                Amazon.txt:1:42: expected a piece in the catalog, found 'Knight', which 'Amazon' builds on"#]],
+            error(&[(
+                "Amazon.txt",
+                "Name: Amazon Image: A.png Combine: Queen Knight\nName: Empress Image: E.png Combine: Amazon",
+            )]),
+        );
+        check(
+            expect![[
+                r#"Pawn.txt:1:49: expected a piece in the catalog, found 'Queen' among the promotions of 'Pawn'"#
+            ]],
+            error(&[(
+                "Pawn.txt",
+                "Name: Pawn Image: Pawn.png Leap: 0 1 Promotion: Queen",
+            )]),
+        );
+        check(
+            expect![[
+                r#"C.txt:1:41: expected pieces that don't build on each other in a cycle, found A -> B -> C -> A"#
+            ]],
+            error(&[
+                ("A.txt", "Name: A Image: A.png Leap: 0 1 Inherits: B"),
+                ("B.txt", "Name: B Image: B.png Leap: 0 1 Combine: C"),
+                ("C.txt", "Name: C Image: C.png Leap: 0 1 Combine: A"),
+            ]),
+        );
+    }
+
+    #[test]
+    fn read_several_pieces_with_comments() {
+        let data = "
+            # The short army
+            Name: ShortRook # moves like a rook, but only so far
+            Image: Rook.png
+            Run+: 1 0 max 3
+            ----------------------------
+            Name: Wazir
+            Image: King.png
+            Betza: W # one step, any orthogonal direction
+            ";
+        let pieces = PieceCatalog::read_pieces(data.as_bytes()).unwrap();
+        check(
+            expect![[r#"
+                ShortRook Rook.png 4
+                Wazir King.png 8"#]],
+            pieces
+                .iter()
+                .map(|parsed| &parsed.piece)
+                .map(|piece| {
+                    format!(
+                        "{} {} {}",
+                        piece.name,
+                        piece.image_key,
+                        piece.move_set.len()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        let error = PieceCatalog::read_pieces("Name: A\n$".as_bytes()).unwrap_err();
+        check(
+            expect![[r#"2:1: expected a statement keyword, found '$'"#]],
+            error.to_string(),
+        );
+    }
+
+    #[test]
+    fn statements_before_the_name_belong_to_the_piece() {
+        let data = "Image: Rook.png Name: Tower Run+: 1 0 Name: Wazir Image: King.png Betza: W";
+        let pieces = PieceCatalog::read_pieces(data.as_bytes()).unwrap();
+        check(
+            expect![[r#"
+                Tower Rook.png 4
+                Wazir King.png 8"#]],
+            pieces
+                .iter()
+                .map(|parsed| &parsed.piece)
+                .map(|piece| {
+                    format!(
+                        "{} {} {}",
+                        piece.name,
+                        piece.image_key,
+                        piece.move_set.len()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+
+    #[test]
+    fn reports_every_problem_with_its_place() {
+        let error = |files: &[(&str, &str)]| catalog_of(files).unwrap_err().to_string();
+        check(
+            expect![[r#"
+                Broken.txt:3:6: expected ':', found '1'
+                Broken.txt:4:8: expected a number, found 'x'
+                Broken.txt:5:10: expected Castle or PawnFirst, found 'Fly'
+                Broken.txt:6:11: expected a statement keyword, found '$'
+                Broken.txt:7:20: expected a first move distance of 2 or more, found '1'
+                Broken.txt:8:6: expected a piece name, found the end of the file"#]],
+            error(&[(
+                "Broken.txt",
+                "Name: Broken\nImage: B.png\nLeap 1 1\nRun: 1 x\nSpecial: Fly\nLeap: 0 1 $\nSpecial: PawnFirst 1\nName:",
+            )]),
+        );
+        check(
+            expect![[r#"
+                Dashes.txt:3:7: expected a number, found '-'
+                Dashes.txt:4:7: expected a number, found '--5'"#]],
+            error(&[(
+                "Dashes.txt",
+                "Name: Dashes Image: D.png\n------\nLeap: - 1\nLeap: --5 1\nLeap: -2 1",
+            )]),
+        );
+        check(
+            expect![[r#"
+                Nameless.txt:1:1: expected a Name statement, found a piece with no name
+                Other.txt:1:1: expected a piece name that isn't taken, found 'Rook', already in Rook.txt
+                Other.txt:3:3: expected at least one move, found none for 'Ghost'"#]],
+            error(&[
+                ("Rook.txt", "Name: Rook Image: Rook.png Run+: 1 0"),
+                (
+                    "Other.txt",
+                    "Name: Rook Image: R.png Run: 0 1\n\n  Name: Ghost Image: G.png",
+                ),
+                ("Nameless.txt", "Leap: 0 1"),
+            ]),
+        );
+        check(
+            expect![[r#"
+                Pawn.txt:1:1: expected an Image statement, found none for 'Pawn'
+                Pawn.txt:1:33: expected a piece in the catalog, found 'Queen' among the promotions of 'Pawn'"#]],
+            error(&[
+                (
+                    "Pawn.txt",
+                    "Name: Pawn Leap: 0 1 Promotion: Queen Promotion: Rook",
+                ),
+                ("Rook.txt", "Name: Rook Image: Rook.png Run+: 1 0"),
+            ]),
+        );
+        check(
+            expect![[r#"
+                Mixed.txt:1:32: expected ':', found '1'
+                Mixed.txt:2:47: expected a piece in the catalog, found 'Queen', which 'Amazon' builds on"#]],
+            error(&[(
+                "Mixed.txt",
+                "Name: Broken Image: B.png Leap 1 1\nName: Amazon Image: A.png Leap: 0 1 Inherits: Queen",
+            )]),
+        );
+    }
+
+    #[test]
+    fn write_pieces_back_out() {
+        let piece = read_piece(
+            b"Name: Fairy_Rook Inherits: Rook Combine: Knight Ferz Royal Value: 7
+            Special: Castle
+            Run+: 1 0 min 2 max 4
+            Leap: 1 1 then Run: 0 1 max 2 then Kill: 0 1
+            Hop: 0 1 CannonKill: 0 1
+            Special: PawnFirst 3 Special: PawnFirst
+            Promotion: Queen Promotion: Great_Bishop",
+        )
+        .unwrap();
+        check(
+            expect![[r#"
+            ----------------------------
+            Name: Fairy_Rook
+            Inherits: Rook
+            Combine: Knight Ferz
+            Royal
+            Value: 7
+            ----------------------------
+            Special: Castle
+            ----------------------------
+            Run: 1 0 min 2 max 4
+            Run: 0 1 min 2 max 4
+            Run: -1 0 min 2 max 4
+            Run: 0 -1 min 2 max 4
+            ----------------------------
+            Leap: 1 1 then Run: 0 1 max 2 then Kill: 0 1
+            ----------------------------
+            Hop: 0 1
+            ----------------------------
+            CannonKill: 0 1
+            ----------------------------
+            Special: PawnFirst 3
+            Special: PawnFirst
+            ----------------------------
+            Promotion: Queen
+            Promotion: Great_Bishop
+            ----------------------------
+        "#]],
+            PieceCatalog::write_piece(&piece),
+        );
+        let written = PieceCatalog::write_piece(&piece);
+        assert_eq!(read_piece(written.as_bytes()).unwrap(), piece);
+    }
+
+    #[test]
+    fn shipped_pieces_read_back_the_same() {
+        let mut paths = fs::read_dir("./chess_pieces/")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert!(!paths.is_empty());
+        for path in paths {
+            let data = fs::read(&path).unwrap();
+            for parsed in PieceCatalog::read_pieces(data.as_slice()).unwrap() {
+                let written = PieceCatalog::write_piece(&parsed.piece);
+                assert_eq!(
+                    read_piece(written.as_bytes()).unwrap(),
+                    parsed.piece,
+                    "{}",
+                    written
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,306 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::{
+    fmt::Display,
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use super::piece::Piece;
+use super::piece_catalog::{PieceCatalog, Symmetry};
+use super::piece_move::{MoveRules, PieceMove};
+use super::piece_value;
+
+/// The image a generated piece shows until it gets one of its own
+const PLACEHOLDER_IMAGE: &str = "Pawn.png";
+
+const SYLLABLES: &[&str] = &[
+    "ka", "ri", "zo", "mel", "tor", "vin", "sha", "dru", "pel", "quo", "ny", "gar",
+];
+
+/// What kinds of piece the generator may invent
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieceGeneratorConfig {
+    /// The balance band: squares reached on average from every square of an
+    /// empty 8x8 board
+    pub mobility: RangeInclusive<f64>,
+    /// How many kinds of move a piece mixes together
+    pub components: RangeInclusive<u32>,
+    /// The longest offset a leap may have on either axis
+    pub max_leap: i32,
+    /// How many pieces to try for one seed before giving up on the band
+    pub attempts: u32,
+}
+
+impl Default for PieceGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            mobility: 3.0..=12.0,
+            components: 1..=3,
+            max_leap: 3,
+            attempts: 200,
+        }
+    }
+}
+
+/// An invented piece with what its balance check measured
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedPiece {
+    pub piece: Piece,
+    pub seed: u64,
+    pub mobility: f64,
+    pub value: u32,
+}
+
+/// Invents pieces from random mixes of leaps, runs with and without a range
+/// limit, and capture-only or move-only moves
+pub struct PieceGenerator {
+    config: PieceGeneratorConfig,
+}
+
+impl PieceGenerator {
+    pub fn new(config: PieceGeneratorConfig) -> Self {
+        Self { config }
+    }
+
+    /// The same seed always gives the same piece. Pieces that can't both move
+    /// and capture, or fall outside the mobility band, are thrown away.
+    pub fn generate(&self, seed: u64) -> Result<GeneratedPiece, crate::Error> {
+        let config = &self.config;
+        if config.components.is_empty() || *config.components.start() == 0 || config.max_leap < 1 {
+            return Err(PieceGenerationError::new(seed, "the config allows no moves").into());
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..config.attempts {
+            let piece = self.random_piece(&mut rng);
+            let captures = piece
+                .move_set
+                .iter()
+                .any(|m| !matches!(m.rules, MoveRules::Leap | MoveRules::Slide));
+            let moves = piece.move_set.iter().any(|m| m.rules != MoveRules::Kill);
+            if !captures || !moves {
+                continue;
+            }
+            let mobility = piece_value::average_mobility(&piece, (8, 8))?;
+            if config.mobility.contains(&mobility) {
+                let value = piece_value::estimate_value(&piece, (8, 8))?;
+                return Ok(GeneratedPiece {
+                    piece,
+                    seed,
+                    mobility,
+                    value,
+                });
+            }
+        }
+        let reason = format!(
+            "nothing within the mobility band after {} tries",
+            config.attempts
+        );
+        Err(PieceGenerationError::new(seed, &reason).into())
+    }
+
+    fn random_piece(&self, rng: &mut StdRng) -> Piece {
+        let mut piece = Piece::new();
+        let syllables = rng.gen_range(2, 4);
+        let name = (0..syllables)
+            .map(|_| *SYLLABLES.choose(rng).unwrap())
+            .collect::<String>();
+        piece.name = name[..1].to_uppercase() + &name[1..];
+        piece.image_key = PLACEHOLDER_IMAGE.to_string();
+
+        let components = rng.gen_range(
+            self.config.components.start(),
+            self.config.components.end() + 1,
+        );
+        for _ in 0..components {
+            let symmetry = *[Symmetry::All, Symmetry::Orthogonal, Symmetry::Mirror]
+                .choose(rng)
+                .unwrap();
+            let (rules, max_leap, max_distance): (&[MoveRules], _, _) = match rng.gen_range(0, 4) {
+                0 => (
+                    &[MoveRules::Leap, MoveRules::Kill],
+                    self.config.max_leap,
+                    None,
+                ),
+                1 => {
+                    let max_distance = if rng.gen() {
+                        Some(rng.gen_range(2, 5))
+                    } else {
+                        None
+                    };
+                    (&[MoveRules::Run], 1, max_distance)
+                }
+                2 => (&[MoveRules::Kill], self.config.max_leap, None),
+                _ => (&[MoveRules::Leap], self.config.max_leap, None),
+            };
+            let forward = rng.gen_range(1, max_leap + 1);
+            let left = rng.gen_range(0, forward + 1);
+            for &rule in rules {
+                for (left, forward) in symmetry.offsets(left, forward) {
+                    let mut piece_move = PieceMove::new(forward, left, rule);
+                    piece_move.max_distance = max_distance;
+                    if !piece.move_set.contains(&piece_move) {
+                        piece.move_set.push(piece_move);
+                    }
+                }
+            }
+        }
+        piece
+    }
+}
+
+/// Writes a generated piece to `<name>.txt` in the directory, with a comment
+/// saying where it came from. A name that already has a file there gets a
+/// number after it, in the file and the piece alike, so nothing is overwritten.
+pub fn write_piece_file(dir: &Path, generated: &GeneratedPiece) -> Result<PathBuf, crate::Error> {
+    fs::create_dir_all(dir)?;
+    let mut piece = generated.piece.clone();
+    let mut path = dir.join(format!("{}.txt", piece.name));
+    let mut number = 1;
+    while path.exists() {
+        number += 1;
+        piece.name = format!("{}{}", generated.piece.name, number);
+        path = dir.join(format!("{}.txt", piece.name));
+    }
+    let text = format!(
+        "# Generated from seed {}: mobility {:.2}, estimated value {}\n{}",
+        generated.seed,
+        generated.mobility,
+        generated.value,
+        PieceCatalog::write_piece(&piece)
+    );
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+#[derive(Debug)]
+pub struct PieceGenerationError {
+    seed: u64,
+    reason: String,
+}
+
+impl PieceGenerationError {
+    fn new(seed: u64, reason: &str) -> Self {
+        Self {
+            seed,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Display for PieceGenerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Cannot generate a piece from seed {}: {}",
+            self.seed, self.reason
+        )
+    }
+}
+
+impl std::error::Error for PieceGenerationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_game::texture_registry;
+    use expect_test::{expect, Expect};
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    #[test]
+    fn a_seed_always_gives_the_same_piece() {
+        let generator = PieceGenerator::new(PieceGeneratorConfig::default());
+        let generated = generator.generate(3).unwrap();
+        check(
+            expect![[r#"
+                mobility 4.91, value 2
+                ----------------------------
+                Name: Druka
+                Image: Pawn.png
+                ----------------------------
+                Kill: 2 3
+                Kill: -2 3
+                ----------------------------
+                Run: 1 1 max 2
+                Run: -1 1 max 2
+                ----------------------------
+                Kill: 1 2
+                Kill: -1 2
+                ----------------------------
+            "#]],
+            format!(
+                "mobility {:.2}, value {}\n{}",
+                generated.mobility,
+                generated.value,
+                PieceCatalog::write_piece(&generated.piece)
+            ),
+        );
+        assert_eq!(generator.generate(3).unwrap(), generated);
+    }
+
+    #[test]
+    fn pieces_stay_within_the_band() {
+        let config = PieceGeneratorConfig {
+            mobility: 5.0..=8.0,
+            ..PieceGeneratorConfig::default()
+        };
+        let generator = PieceGenerator::new(config);
+        for seed in 0..20 {
+            let generated = generator.generate(seed).unwrap();
+            assert!((5.0..=8.0).contains(&generated.mobility));
+            let mobility = piece_value::average_mobility(&generated.piece, (8, 8)).unwrap();
+            assert_eq!(mobility, generated.mobility);
+        }
+        let impossible = PieceGeneratorConfig {
+            mobility: 100.0..=200.0,
+            attempts: 5,
+            ..PieceGeneratorConfig::default()
+        };
+        check(
+            expect![[
+                r#"Cannot generate a piece from seed 1: nothing within the mobility band after 5 tries"#
+            ]],
+            PieceGenerator::new(impossible)
+                .generate(1)
+                .unwrap_err()
+                .to_string(),
+        );
+    }
+
+    #[test]
+    fn generated_pieces_have_an_image() {
+        let generated = PieceGenerator::new(PieceGeneratorConfig::default())
+            .generate(3)
+            .unwrap();
+        let key = texture_registry::texture_key(&generated.piece);
+        let images = texture_registry::image_files("./chess_images").unwrap();
+        assert!(images.iter().any(|(image, _)| image == key), "{}", key);
+    }
+
+    #[test]
+    fn written_pieces_load_into_a_catalog() {
+        let dir = std::env::temp_dir().join("chess-variants-generated-pieces");
+        let _ = fs::remove_dir_all(&dir);
+        let generator = PieceGenerator::new(PieceGeneratorConfig::default());
+        let mut names = vec![];
+        for seed in [10, 11, 12, 12, 13, 14, 12] {
+            let generated = generator.generate(seed).unwrap();
+            let path = write_piece_file(&dir, &generated).unwrap();
+            names.push(path.file_stem().unwrap().to_string_lossy().to_string());
+        }
+        let mut catalog = PieceCatalog::new().unwrap();
+        catalog.generate(dir.to_string_lossy().to_string()).unwrap();
+        for name in &names {
+            let piece = catalog.get_piece(name).unwrap();
+            assert!(!piece.move_set.is_empty());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+        check(
+            expect![[r#"Nygarquo Pelzomel Kashamel Kashamel2 Gardru Torgartor Kashamel3"#]],
+            names.join(" "),
+        );
+    }
+}
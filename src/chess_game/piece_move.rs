@@ -3,19 +3,35 @@ use enum_derive::EnumFromStr;
 use macro_attr::macro_attr;
 use macro_attr::macro_attr_impl;
 
-#[derive(Debug)]
+/// A move a piece may make, relative to the direction its player faces
+///
+/// For `PawnFirst` the `max_distance` field holds how many steps the first move may
+/// take, `DEFAULT_PAWN_FIRST_DISTANCE` when it is not set.
+/// A `Run` or `Slide` only stops on squares `min_distance` to `max_distance` steps away.
+/// Any `legs` are taken in turn from each square this move stops on.
+#[derive(Debug, Clone, PartialEq)]
 pub struct PieceMove {
-    forward: i32,
-    left: i32,
-    rules: MoveRules,
+    pub forward: i32,
+    pub left: i32,
+    pub rules: MoveRules,
+    pub min_distance: u32,
+    pub max_distance: Option<u32>,
+    pub legs: Vec<PieceMove>,
 }
 
+/// How far a `PawnFirst` move reaches when it gives no distance
+pub const DEFAULT_PAWN_FIRST_DISTANCE: u32 = 2;
+
 macro_attr! {
-#[derive(Debug, EnumFromStr!)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumFromStr!)]
 pub enum MoveRules {
     Leap,
     Kill,
     Run,
+    /// A run that only moves, never capturing what blocks it
+    Slide,
+    Hop,
+    CannonKill,
     PawnFirst,
     Castle,
 }
@@ -27,6 +43,9 @@ impl PieceMove {
             forward,
             left,
             rules,
+            min_distance: 1,
+            max_distance: None,
+            legs: vec![],
         }
     }
     pub fn new_special(rules: MoveRules) -> Self {
@@ -34,6 +53,9 @@ impl PieceMove {
             forward: 0,
             left: 0,
             rules,
+            min_distance: 1,
+            max_distance: None,
+            legs: vec![],
         }
     }
 }
@@ -0,0 +1,266 @@
+use std::{collections::HashSet, fmt::Write};
+
+use super::betza;
+use super::board::Board;
+use super::game_piece::GamePiece;
+use super::move_generator::MoveGenerator;
+use super::piece::Piece;
+use super::piece_catalog::PieceCatalog;
+use super::player::{Orientation, Player};
+
+/// How many squares of average mobility and capture reach together are worth a pawn
+const SQUARES_PER_PAWN: f64 = 3.4;
+/// What a piece that only ever reaches half the squares keeps of its worth
+const COLORBOUND_FACTOR: f64 = 0.85;
+/// What a piece that only goes forward keeps of its worth; one that comes
+/// back as far as it goes keeps all of it
+const ONE_WAY_FACTOR: f64 = 0.65;
+/// The piece with no moves that fills the board when measuring capture reach
+const DUMMY_NAME: &str = "(dummy)";
+
+/// How a piece moves about an empty board, and what that makes it worth
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieceAnalysis {
+    pub name: String,
+    /// Squares reached on average from every square of the board, counting
+    /// the squares capture-only moves would take an enemy on
+    pub mobility: f64,
+    /// Enemy pieces attacked on average from every square of a board full of them
+    pub capture_reach: f64,
+    /// Of those, the squares on average ahead of the piece
+    pub forward_reach: f64,
+    /// Of those, the squares on average behind the piece
+    pub backward_reach: f64,
+    /// Whether every move keeps the piece on squares of one color
+    pub colorbound: bool,
+    /// The worth in pawns the moves suggest
+    pub estimate: f64,
+    /// The worth used in play: the piece file's `Value:`, or else the rounded estimate
+    pub value: u32,
+}
+
+/// Moves the piece, facing up, from every square of a board of the given
+/// size: once with the board empty, and once with every other square holding
+/// an enemy piece that doesn't move, which is what its captures reach.
+///
+/// Riders go far on the empty board but capture only what stands next to them
+/// on the full one, so counting both keeps them from looking worth more than
+/// they are against leapers.
+pub fn analyze(piece: &Piece, (width, height): (u32, u32)) -> Result<PieceAnalysis, crate::Error> {
+    // The piece gets a catalog of its own, so pieces outside any catalog work too
+    let mut catalog = PieceCatalog::new()?;
+    catalog.catalog.insert(piece.name.clone(), piece.clone());
+    let mut dummy = Piece::new();
+    dummy.name = DUMMY_NAME.to_string();
+    catalog.catalog.insert(dummy.name.clone(), dummy);
+    let mut empty = Board::new()?;
+    empty.resize(width, height)?;
+    empty
+        .players
+        .push(Player::new("white".to_string(), Orientation::Up));
+    empty
+        .players
+        .push(Player::new("black".to_string(), Orientation::Down));
+    let mut crowded = empty.clone();
+    for horz in 1..=width {
+        for vert in 1..=height {
+            crowded.place_piece(GamePiece::new(
+                DUMMY_NAME.to_string(),
+                "black".to_string(),
+                horz,
+                vert,
+            )?);
+        }
+    }
+    let (mut reach, mut captures, mut forward, mut backward) = (0, 0, 0, 0);
+    let mut colorbound = true;
+    for horz in 1..=width {
+        for vert in 1..=height {
+            let game_piece = GamePiece::new(piece.name.clone(), "white".to_string(), horz, vert)?;
+            empty.place_piece(game_piece.clone());
+            let dummy = crowded.take_piece(horz, vert).unwrap();
+            crowded.place_piece(game_piece.clone());
+            let mut targets = HashSet::new();
+            for board_move in MoveGenerator::new(&empty, &catalog).piece_moves(&game_piece)? {
+                targets.insert(board_move.to);
+            }
+            let mut attacked = HashSet::new();
+            for board_move in MoveGenerator::new(&crowded, &catalog).piece_moves(&game_piece)? {
+                attacked.insert(board_move.to);
+            }
+            captures += attacked.len();
+            for (to_horz, to_vert) in targets.union(&attacked) {
+                reach += 1;
+                if *to_vert > vert {
+                    forward += 1;
+                } else if *to_vert < vert {
+                    backward += 1;
+                }
+                if (to_horz + to_vert) % 2 != (horz + vert) % 2 {
+                    colorbound = false;
+                }
+            }
+            empty.take_piece(horz, vert);
+            crowded.take_piece(horz, vert);
+            crowded.place_piece(dummy);
+        }
+    }
+
+    let squares = (width * height) as f64;
+    let mobility = reach as f64 / squares;
+    let capture_reach = captures as f64 / squares;
+    let mut estimate = (mobility + capture_reach) / SQUARES_PER_PAWN;
+    // A piece that can't come back is worth less
+    if forward > 0 {
+        let comes_back = (backward as f64 / forward as f64).min(1.0);
+        estimate *= ONE_WAY_FACTOR + (1.0 - ONE_WAY_FACTOR) * comes_back;
+    }
+    if colorbound && reach > 0 {
+        estimate *= COLORBOUND_FACTOR;
+    }
+    Ok(PieceAnalysis {
+        name: piece.name.clone(),
+        mobility,
+        capture_reach,
+        forward_reach: forward as f64 / squares,
+        backward_reach: backward as f64 / squares,
+        colorbound: colorbound && reach > 0,
+        estimate,
+        value: piece
+            .value
+            .unwrap_or_else(|| (estimate.round() as u32).max(1)),
+    })
+}
+
+/// How many squares a piece reaches on average from every square of an empty
+/// board, standing alone and facing up
+pub fn average_mobility(piece: &Piece, size: (u32, u32)) -> Result<f64, crate::Error> {
+    Ok(analyze(piece, size)?.mobility)
+}
+
+/// What a piece is worth in pawns on a board of the given size
+pub fn estimate_value(piece: &Piece, size: (u32, u32)) -> Result<u32, crate::Error> {
+    Ok(analyze(piece, size)?.value)
+}
+
+/// A table of every piece in the catalog by name, analyzed on a board of the
+/// given size
+pub fn catalog_report(catalog: &PieceCatalog, size: (u32, u32)) -> Result<String, crate::Error> {
+    let mut pieces = catalog.catalog.values().collect::<Vec<_>>();
+    pieces.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut report = format!(
+        "{:<14}{:>6}{:>10}{:>10}{:>10}{:>9}{:>10}  {:<12}{}\n",
+        "Piece",
+        "Value",
+        "Estimate",
+        "Mobility",
+        "Captures",
+        "Forward",
+        "Backward",
+        "Colorbound",
+        "Betza"
+    );
+    for piece in pieces {
+        let analysis = analyze(piece, size)?;
+        let value = match piece.value {
+            Some(value) => format!("{}*", value),
+            None => analysis.value.to_string(),
+        };
+        // Moves Betza notation can't express, such as multi-leg moves, leave a dash
+        let notation = betza::from_piece(piece).unwrap_or_else(|_| String::from("-"));
+        writeln!(
+            report,
+            "{:<14}{:>6}{:>10.2}{:>10.2}{:>10.2}{:>9.2}{:>10.2}  {:<12}{}",
+            analysis.name,
+            value,
+            analysis.estimate,
+            analysis.mobility,
+            analysis.capture_reach,
+            analysis.forward_reach,
+            analysis.backward_reach,
+            if analysis.colorbound { "yes" } else { "no" },
+            notation
+        )
+        .unwrap();
+    }
+    report.push_str("* set by the piece file\n");
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_game::test_support::catalog;
+    use expect_test::{expect, Expect};
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    #[test]
+    fn prices_the_classic_pieces() {
+        let catalog = catalog();
+        let mut names = catalog.catalog.keys().collect::<Vec<_>>();
+        names.sort();
+        let points = names
+            .iter()
+            .map(|name| {
+                let points = estimate_value(&catalog.catalog[*name], (8, 8)).unwrap();
+                format!("{} {}", name, points)
+            })
+            .collect::<Vec<_>>();
+        check(
+            expect![[r#"
+            Bishop 3
+            King 4
+            Knight 3
+            Pawn 1
+            Queen 9
+            Rook 5"#]],
+            points.join("\n"),
+        );
+    }
+
+    #[test]
+    fn reports_the_classic_pieces() {
+        check(
+            expect![[r#"
+            Piece          Value  Estimate  Mobility  Captures  Forward  Backward  Colorbound  Betza
+            Bishop             3      2.95      8.75      3.06     4.38      4.38  yes         B
+            King               4      3.86      6.56      6.56     2.41      2.41  no          KO
+            Knight             3      3.09      5.25      5.25     2.62      2.62  no          N
+            Pawn               1      0.90      3.16      1.53     3.16      0.00  no          fmWfcFifmW2
+            Queen              9      8.62     22.75      6.56     7.88      7.88  no          Q
+            Rook               5      5.15     14.00      3.50     3.50      3.50  no          R
+            * set by the piece file
+            "#]],
+            catalog_report(&catalog(), (8, 8)).unwrap(),
+        );
+    }
+
+    #[test]
+    fn small_boards_and_value_overrides() {
+        let mut catalog = catalog();
+        let report = catalog_report(&catalog, (5, 5)).unwrap();
+        check(
+            expect![[r#"
+            Piece          Value  Estimate  Mobility  Captures  Forward  Backward  Colorbound  Betza
+            Bishop             2      1.84      4.80      2.56     2.40      2.40  yes         B
+            King               3      3.39      5.76      5.76     2.08      2.08  no          KO
+            Knight             2      2.26      3.84      3.84     1.92      1.92  no          N
+            Pawn               1      0.76      2.68      1.28     2.68      0.00  no          fmWfcFifmW2
+            Queen              5      5.46     12.80      5.76     4.40      4.40  no          Q
+            Rook               3      3.29      8.00      3.20     2.00      2.00  no          R
+            * set by the piece file
+            "#]],
+            report,
+        );
+
+        let mut piece = catalog.catalog["Knight"].clone();
+        piece.value = Some(3);
+        assert_eq!(estimate_value(&piece, (8, 8)).unwrap(), 3);
+        catalog.catalog.insert(piece.name.clone(), piece);
+        let report = catalog_report(&catalog, (8, 8)).unwrap();
+        assert!(report.contains("Knight            3*"), "{}", report);
+    }
+}
@@ -0,0 +1,104 @@
+/// The board direction a player's pieces treat as "forward"
+///
+/// Directions are in board coordinates, so `Up` points towards higher ranks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    #[default]
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Orientation {
+    pub fn from_name(name: &str) -> Option<Orientation> {
+        match name.to_ascii_lowercase().as_str() {
+            "up" => Some(Orientation::Up),
+            "down" => Some(Orientation::Down),
+            "left" => Some(Orientation::Left),
+            "right" => Some(Orientation::Right),
+            "up-left" => Some(Orientation::UpLeft),
+            "up-right" => Some(Orientation::UpRight),
+            "down-left" => Some(Orientation::DownLeft),
+            "down-right" => Some(Orientation::DownRight),
+            _ => None,
+        }
+    }
+
+    /// The name board files use for the orientation, as read by `from_name`
+    pub fn name(self) -> &'static str {
+        match self {
+            Orientation::Up => "up",
+            Orientation::Down => "down",
+            Orientation::Left => "left",
+            Orientation::Right => "right",
+            Orientation::UpLeft => "up-left",
+            Orientation::UpRight => "up-right",
+            Orientation::DownLeft => "down-left",
+            Orientation::DownRight => "down-right",
+        }
+    }
+
+    /// Maps a piece-relative (forward, left) offset onto a board offset
+    pub fn resolve(self, forward: i32, left: i32) -> (i32, i32) {
+        let (forward_dir, left_dir) = match self {
+            Orientation::Up | Orientation::UpLeft | Orientation::UpRight => ((0, 1), (-1, 0)),
+            Orientation::Down | Orientation::DownLeft | Orientation::DownRight => ((0, -1), (1, 0)),
+            Orientation::Left => ((-1, 0), (0, -1)),
+            Orientation::Right => ((1, 0), (0, 1)),
+        };
+        let offset = (
+            forward * forward_dir.0 + left * left_dir.0,
+            forward * forward_dir.1 + left * left_dir.1,
+        );
+        match self {
+            Orientation::UpLeft | Orientation::DownRight => rotate_eighth(offset, true),
+            Orientation::UpRight | Orientation::DownLeft => rotate_eighth(offset, false),
+            _ => offset,
+        }
+    }
+}
+
+/// Turns an offset by one eighth of a turn by walking it around its square
+/// ring, so leaps keep their distance and stay on whole squares.
+fn rotate_eighth((mut x, mut y): (i32, i32), counter_clockwise: bool) -> (i32, i32) {
+    let r = x.abs().max(y.abs());
+    for _ in 0..r {
+        if counter_clockwise {
+            if x == r && y < r {
+                y += 1;
+            } else if y == r && x > -r {
+                x -= 1;
+            } else if x == -r && y > -r {
+                y -= 1;
+            } else {
+                x += 1;
+            }
+        } else if x == r && y > -r {
+            y -= 1;
+        } else if y == -r && x > -r {
+            x -= 1;
+        } else if x == -r && y < r {
+            y += 1;
+        } else {
+            x += 1;
+        }
+    }
+    (x, y)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    pub name: String,
+    pub orientation: Orientation,
+}
+
+impl Player {
+    pub fn new(name: String, orientation: Orientation) -> Self {
+        Self { name, orientation }
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt::Display;
+
+use super::player::Orientation;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromotionArea {
+    /// The last `n` ranks in the direction the player faces
+    Ranks(u32),
+    Square(u32, u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromotionZone {
+    pub team_name: String,
+    pub area: PromotionArea,
+}
+
+impl PromotionZone {
+    pub fn contains(
+        &self,
+        (horz_pos, vert_pos): (u32, u32),
+        orientation: Orientation,
+        (width, height): (u32, u32),
+    ) -> bool {
+        match self.area {
+            PromotionArea::Square(horz, vert) => (horz, vert) == (horz_pos, vert_pos),
+            PromotionArea::Ranks(n) => {
+                let top = vert_pos > height.saturating_sub(n);
+                let bottom = vert_pos <= n;
+                let right = horz_pos > width.saturating_sub(n);
+                let left = horz_pos <= n;
+                match orientation {
+                    Orientation::Up => top,
+                    Orientation::Down => bottom,
+                    Orientation::Left => left,
+                    Orientation::Right => right,
+                    Orientation::UpLeft => top || left,
+                    Orientation::UpRight => top || right,
+                    Orientation::DownLeft => bottom || left,
+                    Orientation::DownRight => bottom || right,
+                }
+            }
+        }
+    }
+}
+
+/// A piece that reached its promotion zone and is waiting for its owner to pick
+/// what it becomes
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingPromotion {
+    pub position: (u32, u32),
+    pub team_name: String,
+    pub choices: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum PromotionError {
+    Pending,
+    NotPending,
+    InvalidChoice(String),
+}
+
+impl Display for PromotionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PromotionError::Pending => write!(f, "A promotion choice must be made first"),
+            PromotionError::NotPending => write!(f, "No piece is waiting to promote"),
+            PromotionError::InvalidChoice(name) => write!(f, "Cannot promote to: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for PromotionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_game::move_generator::{BoardMove, MoveKind};
+    use crate::chess_game::test_support::{board, catalog};
+
+    #[test]
+    fn ranks_follow_orientation() {
+        let zone = PromotionZone {
+            team_name: "black".to_string(),
+            area: PromotionArea::Ranks(2),
+        };
+        assert!(zone.contains((3, 2), Orientation::Down, (8, 8)));
+        assert!(!zone.contains((3, 3), Orientation::Down, (8, 8)));
+        assert!(zone.contains((3, 7), Orientation::Up, (8, 8)));
+        assert!(zone.contains((8, 1), Orientation::Right, (8, 8)));
+    }
+
+    #[test]
+    fn pawn_waits_for_promotion_choice() {
+        let catalog = catalog();
+        let mut board = board(&[("Pawn", "white", 1, 7), ("Pawn", "black", 8, 7)]);
+        board.promotion_zones.push(PromotionZone {
+            team_name: "white".to_string(),
+            area: PromotionArea::Ranks(1),
+        });
+        let board_move = BoardMove {
+            from: (1, 7),
+            to: (1, 8),
+            kind: MoveKind::Move,
+        };
+        board.make_move(&catalog, &board_move).unwrap();
+        assert_eq!(
+            board.pending_promotion.as_ref().unwrap().choices,
+            vec!["Queen", "Rook", "Bishop", "Knight"]
+        );
+
+        let blocked = BoardMove {
+            from: (8, 7),
+            to: (8, 6),
+            kind: MoveKind::Move,
+        };
+        assert!(board.make_move(&catalog, &blocked).is_err());
+        assert!(board.choose_promotion("King").is_err());
+
+        board.choose_promotion("Knight").unwrap();
+        assert_eq!(board.get_piece(1, 8).unwrap().piece_name, "Knight");
+        assert!(board.pending_promotion.is_none());
+        assert!(board.choose_promotion("Queen").is_err());
+    }
+}
@@ -0,0 +1,607 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::fmt::Display;
+
+use super::board::Board;
+use super::game_piece::GamePiece;
+use super::piece_catalog::PieceCatalog;
+use super::player::Player;
+
+/// How many random arrangements a seed tries before giving up on the constraints
+const SHUFFLE_ATTEMPTS: usize = 1000;
+
+/// How many starting positions Chess960 numbers
+const CHESS960_POSITIONS: usize = 960;
+/// Where the two knights go among the five squares left once the bishops and
+/// the queen stand, in Scharnagl's order
+const KNIGHT_SQUARES: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// The square a piece starts on and the piece's name
+type StartingPiece = ((u32, u32), String);
+/// A player's name with the squares and pieces that get shuffled
+type Side = (String, Vec<(u32, u32)>, Vec<String>);
+
+/// Which arrangement a shuffle picks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShufflePosition {
+    /// A random arrangement that fits the constraints
+    Seed(u64),
+    /// The arrangement at this place among every one that fits, counting in
+    /// alphabetical order of the piece names square by square; unlike
+    /// `Chess960` this works for any army
+    Index(usize),
+    /// The Chess960 position with this number in Scharnagl's numbering, where
+    /// 518 is the usual setup. The back rank must hold the classic eight pieces.
+    Chess960(usize),
+}
+
+/// Shuffles the pieces on each player's back rank among the squares they
+/// start on, like Chess960 does
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShuffleConfig {
+    pub position: ShufflePosition,
+    /// Piece kinds that keep their squares even on the back rank, like pawns
+    pub fixed: Vec<String>,
+    /// Piece kinds whose pieces must stand on both square colors, like bishops
+    pub opposite_colors: Vec<String>,
+    /// Royal pieces stay between two castle partners when there are two
+    pub royal_between_partners: bool,
+    /// Every player gets the first player's arrangement instead of one of their own
+    pub mirrored: bool,
+}
+
+impl ShuffleConfig {
+    /// The Chess960 rules: bishops on both colors, the king between the rooks
+    /// and the same arrangement for every player
+    pub fn chess960(position: ShufflePosition) -> Self {
+        Self {
+            position,
+            fixed: vec![String::from("Pawn")],
+            opposite_colors: vec![String::from("Bishop")],
+            royal_between_partners: true,
+            mirrored: true,
+        }
+    }
+}
+
+/// Rearranges the board's starting pieces as the config says
+pub fn shuffle_board(
+    board: &mut Board,
+    catalog: &PieceCatalog,
+    config: &ShuffleConfig,
+) -> Result<(), crate::Error> {
+    let mut rng = StdRng::seed_from_u64(match config.position {
+        ShufflePosition::Seed(seed) => seed,
+        _ => 0,
+    });
+    let mut sides: Vec<Side> = vec![];
+    for player in &board.players {
+        let (squares, names): (Vec<_>, Vec<_>) =
+            shuffled_pieces(board, config, player).into_iter().unzip();
+        if !names.is_empty() {
+            sides.push((player.name.clone(), squares, names));
+        }
+    }
+    if !config.mirrored {
+        for (team_name, squares, names) in &sides {
+            let fits = |names: &[String]| fits(catalog, config, squares, names);
+            let arrangement = arrange(&mut rng, config, team_name, names.clone(), fits)?;
+            place(board, team_name, squares, &arrangement)?;
+        }
+        return Ok(());
+    }
+    let (first_team, _, first) = match sides.first() {
+        Some(side) => side,
+        None => return Ok(()),
+    };
+    let mut theirs = first.clone();
+    theirs.sort();
+    for (team_name, _, names) in &sides[1..] {
+        let mut mine = names.clone();
+        mine.sort();
+        if mine != theirs {
+            return Err(ShuffleError::ArmiesDiffer(team_name.clone()).into());
+        }
+    }
+    // Every side gets the arrangement on its own squares, so it has to fit
+    // each of them and not just the first
+    let fits = |names: &[String]| {
+        sides
+            .iter()
+            .all(|(_, squares, _)| fits(catalog, config, squares, names))
+    };
+    let arrangement = arrange(&mut rng, config, first_team, first.clone(), fits)?;
+    for (team_name, squares, _) in &sides {
+        place(board, team_name, squares, &arrangement)?;
+    }
+    Ok(())
+}
+
+/// Picks the arrangement the config's position asks for
+fn arrange(
+    rng: &mut StdRng,
+    config: &ShuffleConfig,
+    team_name: &str,
+    names: Vec<String>,
+    fits: impl Fn(&[String]) -> bool,
+) -> Result<Vec<String>, crate::Error> {
+    match config.position {
+        ShufflePosition::Seed(_) => random_arrangement(rng, names, fits),
+        ShufflePosition::Index(index) => indexed_arrangement(names, fits, index),
+        ShufflePosition::Chess960(number) => chess960_arrangement(team_name, names, number),
+    }
+}
+
+/// Puts the team's pieces on the squares in the order given
+fn place(
+    board: &mut Board,
+    team_name: &str,
+    squares: &[(u32, u32)],
+    arrangement: &[String],
+) -> Result<(), crate::Error> {
+    for (&(horz, vert), piece_name) in squares.iter().zip(arrangement) {
+        board.take_piece(horz, vert);
+        board.place_piece(GamePiece::new(
+            piece_name.clone(),
+            team_name.to_string(),
+            horz,
+            vert,
+        )?);
+    }
+    Ok(())
+}
+
+/// The player's pieces on their back rank, the row furthest behind as they
+/// face, leaving out the fixed kinds, by square in board order
+fn shuffled_pieces(board: &Board, config: &ShuffleConfig, player: &Player) -> Vec<StartingPiece> {
+    let (horz, vert) = player.orientation.resolve(1, 0);
+    let ahead = |game_piece: &GamePiece| {
+        game_piece.horz_position as i32 * horz + game_piece.vert_position as i32 * vert
+    };
+    let team = board
+        .collect_game_pieces()
+        .into_iter()
+        .filter(|game_piece| game_piece.team_name == player.name)
+        .collect::<Vec<_>>();
+    let back = match team.iter().map(|game_piece| ahead(game_piece)).min() {
+        Some(back) => back,
+        None => return vec![],
+    };
+    let mut pieces = team
+        .into_iter()
+        .filter(|game_piece| {
+            ahead(game_piece) == back
+                && !config
+                    .fixed
+                    .iter()
+                    .any(|kind| kind.eq_ignore_ascii_case(&game_piece.piece_name))
+        })
+        .map(|game_piece| {
+            (
+                (game_piece.horz_position, game_piece.vert_position),
+                game_piece.piece_name.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    pieces.sort();
+    pieces
+}
+
+/// Whether an arrangement of piece names over the squares keeps to the config
+fn fits(
+    catalog: &PieceCatalog,
+    config: &ShuffleConfig,
+    squares: &[(u32, u32)],
+    names: &[String],
+) -> bool {
+    for kind in &config.opposite_colors {
+        let colors = squares
+            .iter()
+            .zip(names)
+            .filter(|(_, name)| name.eq_ignore_ascii_case(kind))
+            .map(|((horz, vert), _)| (horz + vert) % 2)
+            .collect::<Vec<_>>();
+        if colors.len() >= 2 && !(colors.contains(&0) && colors.contains(&1)) {
+            return false;
+        }
+    }
+    if config.royal_between_partners {
+        let flags = names
+            .iter()
+            .map(|name| {
+                catalog
+                    .get_piece(name)
+                    .map(|piece| (piece.royal, piece.castle_partner))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let partners = flags.iter().filter(|(_, partner)| *partner).count();
+        for (i, (royal, _)) in flags.iter().enumerate() {
+            if *royal
+                && partners >= 2
+                && !(flags[..i].iter().any(|(_, p)| *p) && flags[i + 1..].iter().any(|(_, p)| *p))
+            {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn random_arrangement(
+    rng: &mut StdRng,
+    mut names: Vec<String>,
+    fits: impl Fn(&[String]) -> bool,
+) -> Result<Vec<String>, crate::Error> {
+    for _ in 0..SHUFFLE_ATTEMPTS {
+        names.shuffle(rng);
+        if fits(&names) {
+            return Ok(names);
+        }
+    }
+    Err(ShuffleError::NoArrangement.into())
+}
+
+fn indexed_arrangement(
+    mut names: Vec<String>,
+    fits: impl Fn(&[String]) -> bool,
+    index: usize,
+) -> Result<Vec<String>, crate::Error> {
+    names.sort();
+    let mut count = 0;
+    loop {
+        if fits(&names) {
+            if count == index {
+                return Ok(names);
+            }
+            count += 1;
+        }
+        if !next_permutation(&mut names) {
+            return Err(ShuffleError::IndexOutOfRange { index, count }.into());
+        }
+    }
+}
+
+/// Lays out the Chess960 position with Scharnagl's number: the bishops on
+/// the light and then the dark squares, the queen and the knights on what's
+/// left, and the rooks around the king on the last three squares
+fn chess960_arrangement(
+    team_name: &str,
+    names: Vec<String>,
+    number: usize,
+) -> Result<Vec<String>, crate::Error> {
+    if number >= CHESS960_POSITIONS {
+        return Err(ShuffleError::IndexOutOfRange {
+            index: number,
+            count: CHESS960_POSITIONS,
+        }
+        .into());
+    }
+    let mut army = names.clone();
+    army.sort();
+    let classic = [
+        "Bishop", "Bishop", "King", "Knight", "Knight", "Queen", "Rook", "Rook",
+    ];
+    if army != classic {
+        return Err(ShuffleError::NotClassicArmy(team_name.to_string()).into());
+    }
+    let mut rank: Vec<Option<&str>> = vec![None; 8];
+    let mut rest = number;
+    rank[rest % 4 * 2 + 1] = Some("Bishop");
+    rest /= 4;
+    rank[rest % 4 * 2] = Some("Bishop");
+    rest /= 4;
+    let empty = |rank: &[Option<&str>]| {
+        (0..rank.len())
+            .filter(|&i| rank[i].is_none())
+            .collect::<Vec<_>>()
+    };
+    let queen = empty(&rank)[rest % 6];
+    rank[queen] = Some("Queen");
+    rest /= 6;
+    let (first, second) = KNIGHT_SQUARES[rest];
+    let squares = empty(&rank);
+    rank[squares[first]] = Some("Knight");
+    rank[squares[second]] = Some("Knight");
+    for (square, name) in empty(&rank).into_iter().zip(["Rook", "King", "Rook"]) {
+        rank[square] = Some(name);
+    }
+    Ok(rank
+        .into_iter()
+        .map(|name| name.unwrap().to_string())
+        .collect())
+}
+
+/// Steps to the next arrangement in lexicographic order, returning false
+/// after the last one
+fn next_permutation(names: &mut [String]) -> bool {
+    let pivot = match (1..names.len()).rev().find(|&i| names[i - 1] < names[i]) {
+        Some(i) => i - 1,
+        None => return false,
+    };
+    let successor = (pivot + 1..names.len())
+        .rev()
+        .find(|&i| names[i] > names[pivot])
+        .unwrap();
+    names.swap(pivot, successor);
+    names[pivot + 1..].reverse();
+    true
+}
+
+#[derive(Debug)]
+pub enum ShuffleError {
+    NoArrangement,
+    IndexOutOfRange { index: usize, count: usize },
+    ArmiesDiffer(String),
+    NotClassicArmy(String),
+}
+
+impl Display for ShuffleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShuffleError::NoArrangement => {
+                write!(f, "No arrangement of the pieces fits the shuffle rules")
+            }
+            ShuffleError::IndexOutOfRange { index, count } => write!(
+                f,
+                "Shuffle position {} is out of range; there are {} positions",
+                index, count
+            ),
+            ShuffleError::ArmiesDiffer(team) => write!(
+                f,
+                "Cannot mirror the shuffle: {} has different pieces from the first player",
+                team
+            ),
+            ShuffleError::NotClassicArmy(team) => write!(
+                f,
+                "Chess960 numbers need a king, a queen and two each of rooks, bishops and knights on the back rank, which {} doesn't have",
+                team
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShuffleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_game::test_support::{board, catalog};
+    use expect_test::{expect, Expect};
+    use std::path::Path;
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    fn classic(config: &ShuffleConfig) -> Result<Board, crate::Error> {
+        let mut board = Board::new()?;
+        board.generate(
+            Path::new("./chess_boards/classic_chess.txt"),
+            &catalog(),
+            Some(config),
+        )?;
+        Ok(board)
+    }
+
+    fn rank(board: &Board, vert: u32) -> String {
+        (1..=board.width)
+            .map(|horz| {
+                let name = &board.get_piece(horz, vert).unwrap().piece_name;
+                if name == "Knight" {
+                    'N'
+                } else {
+                    name.chars().next().unwrap()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chess960_positions_by_number() {
+        let ranks = [0, 1, 518, 959]
+            .iter()
+            .map(|&number| {
+                let config = ShuffleConfig::chess960(ShufflePosition::Chess960(number));
+                let board = classic(&config).unwrap();
+                assert_eq!(rank(&board, 1), rank(&board, 8));
+                format!("{} {}", number, rank(&board, 1))
+            })
+            .collect::<Vec<_>>();
+        check(
+            expect![[r#"
+            0 BBQNNRKR
+            1 BQNBNRKR
+            518 RNBQKBNR
+            959 RKRNNQBB"#]],
+            ranks.join("\n"),
+        );
+
+        let config = ShuffleConfig::chess960(ShufflePosition::Chess960(960));
+        check(
+            expect![[r#"Shuffle position 960 is out of range; there are 960 positions"#]],
+            classic(&config).unwrap_err().to_string(),
+        );
+        let mut board = board(&[("Rook", "white", 1, 1), ("King", "white", 5, 1)]);
+        let config = ShuffleConfig::chess960(ShufflePosition::Chess960(518));
+        check(
+            expect![[
+                r#"Chess960 numbers need a king, a queen and two each of rooks, bishops and knights on the back rank, which white doesn't have"#
+            ]],
+            shuffle_board(&mut board, &catalog(), &config)
+                .unwrap_err()
+                .to_string(),
+        );
+    }
+
+    #[test]
+    fn chess960_positions_by_index() {
+        let ranks = [0, 1, 518, 959]
+            .iter()
+            .map(|&index| {
+                let config = ShuffleConfig::chess960(ShufflePosition::Index(index));
+                let board = classic(&config).unwrap();
+                assert_eq!(rank(&board, 1), rank(&board, 8));
+                format!("{} {}", index, rank(&board, 1))
+            })
+            .collect::<Vec<_>>();
+        check(
+            expect![[r#"
+            0 BBNNQRKR
+            1 BBNNRKQR
+            518 QNBRKNRB
+            959 RQNNKRBB"#]],
+            ranks.join("\n"),
+        );
+
+        let config = ShuffleConfig::chess960(ShufflePosition::Index(960));
+        check(
+            expect![[r#"Shuffle position 960 is out of range; there are 960 positions"#]],
+            classic(&config).unwrap_err().to_string(),
+        );
+    }
+
+    #[test]
+    fn seeded_shuffles_keep_the_rules() {
+        for seed in 0..30 {
+            let config = ShuffleConfig::chess960(ShufflePosition::Seed(seed));
+            let board = classic(&config).unwrap();
+            assert_eq!(board, classic(&config).unwrap());
+            let back = rank(&board, 1);
+            assert_eq!(back, rank(&board, 8));
+            assert_eq!(rank(&board, 2), "PPPPPPPP");
+            let bishops = back
+                .match_indices('B')
+                .map(|(i, _)| i % 2)
+                .collect::<Vec<_>>();
+            assert_ne!(bishops[0], bishops[1], "{}", back);
+            let king = back.find('K').unwrap();
+            assert!(
+                back[..king].contains('R') && back[king..].contains('R'),
+                "{}",
+                back
+            );
+        }
+    }
+
+    #[test]
+    fn mirrored_shuffles_fit_every_side() {
+        // Black's squares aren't colored like white's, so an arrangement that
+        // fits white can put black's bishops on one color
+        let mut pieces = vec![];
+        for (i, &horz) in [1, 2, 3, 4].iter().enumerate() {
+            pieces.push((
+                ["Bishop", "Bishop", "Knight", "Knight"][i],
+                "white",
+                horz,
+                1,
+            ));
+        }
+        for (i, &horz) in [1, 2, 3, 5].iter().enumerate() {
+            pieces.push((
+                ["Bishop", "Bishop", "Knight", "Knight"][i],
+                "black",
+                horz,
+                8,
+            ));
+        }
+        for seed in 0..20 {
+            let mut board = board(&pieces);
+            let config = ShuffleConfig::chess960(ShufflePosition::Seed(seed));
+            shuffle_board(&mut board, &catalog(), &config).unwrap();
+            for vert in [1, 8] {
+                let colors = board
+                    .collect_game_pieces()
+                    .iter()
+                    .filter(|p| p.vert_position == vert && p.piece_name == "Bishop")
+                    .map(|p| (p.horz_position + vert) % 2)
+                    .collect::<Vec<_>>();
+                assert_ne!(colors[0], colors[1], "seed {} rank {}", seed, vert);
+            }
+        }
+    }
+
+    #[test]
+    fn independent_sides_differ() {
+        let config = ShuffleConfig {
+            mirrored: false,
+            ..ShuffleConfig::chess960(ShufflePosition::Seed(4))
+        };
+        let board = classic(&config).unwrap();
+        check(
+            expect![[r#"
+                RKRNQBBN
+                BNRBNKRQ"#]],
+            format!("{}\n{}", rank(&board, 8), rank(&board, 1)),
+        );
+    }
+
+    #[test]
+    fn only_back_rank_pieces_move() {
+        let mut board = board(&[
+            ("Rook", "white", 1, 1),
+            ("Knight", "white", 2, 1),
+            ("King", "white", 5, 1),
+            ("Pawn", "white", 7, 1),
+            ("Rook", "white", 8, 1),
+            ("Knight", "white", 4, 3),
+            ("Rook", "black", 1, 8),
+            ("King", "black", 5, 8),
+            ("Bishop", "black", 6, 7),
+        ]);
+        let config = ShuffleConfig {
+            mirrored: false,
+            ..ShuffleConfig::chess960(ShufflePosition::Seed(2))
+        };
+        shuffle_board(&mut board, &catalog(), &config).unwrap();
+        let mut pieces = board
+            .collect_game_pieces()
+            .iter()
+            .map(|p| format!("{} {} {}", p.piece_name, p.horz_position, p.vert_position))
+            .collect::<Vec<_>>();
+        pieces.sort();
+        check(
+            expect![[r#"
+            Bishop 6 7
+            King 5 1
+            King 5 8
+            Knight 1 1
+            Knight 4 3
+            Pawn 7 1
+            Rook 1 8
+            Rook 2 1
+            Rook 8 1"#]],
+            pieces.join("\n"),
+        );
+    }
+
+    #[test]
+    fn shuffles_four_player_boards() {
+        let mut board = Board::new().unwrap();
+        let config = ShuffleConfig::chess960(ShufflePosition::Seed(9));
+        board
+            .generate(
+                Path::new("./chess_boards/four_player_chess.txt"),
+                &catalog(),
+                Some(&config),
+            )
+            .unwrap();
+        let blue = (4..=11)
+            .map(|vert| board.get_piece(1, vert).unwrap().piece_name.clone())
+            .collect::<Vec<_>>();
+        let red = (4..=11)
+            .map(|horz| board.get_piece(horz, 1).unwrap().piece_name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(blue, red);
+    }
+}
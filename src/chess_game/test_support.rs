@@ -0,0 +1,26 @@
+use super::board::Board;
+use super::game_piece::GamePiece;
+use super::piece_catalog::PieceCatalog;
+use super::player::{Orientation, Player};
+
+pub fn catalog() -> PieceCatalog {
+    let mut catalog = PieceCatalog::new().unwrap();
+    catalog.generate("./chess_pieces/".to_string()).unwrap();
+    catalog
+}
+
+/// An 8x8 board with white facing up and black facing down
+pub fn board(pieces: &[(&str, &str, u32, u32)]) -> Board {
+    let mut board = Board::new().unwrap();
+    board.resize(8, 8).unwrap();
+    board
+        .players
+        .push(Player::new("white".to_string(), Orientation::Up));
+    board
+        .players
+        .push(Player::new("black".to_string(), Orientation::Down));
+    for &(piece, team, horz, vert) in pieces {
+        board.place_piece(GamePiece::new(piece.to_string(), team.to_string(), horz, vert).unwrap());
+    }
+    board
+}
@@ -1,5 +1,7 @@
 use crate::sdl_error::ToSdl;
+use lazy_static::lazy_static;
 use parking_lot::RwLock;
+use regex::Regex;
 use sdl2::{
     image::LoadTexture,
     pixels::Color,
@@ -9,6 +11,8 @@ use sdl2::{
 use std::{collections::HashMap, fmt::Display, fs, path::PathBuf, rc::Rc};
 
 use super::board::Board;
+use super::piece::Piece;
+use super::piece_catalog::PieceCatalog;
 
 pub struct TextureRegistry<'tc, C> {
     pub texture_creator: &'tc TextureCreator<C>,
@@ -68,30 +72,28 @@ impl<'tc, C> TextureRegistry<'tc, C> {
     }
 
     pub fn generate_piece_images(&mut self, dir_path: String) -> Result<(), crate::Error> {
-        let dir = fs::read_dir(&dir_path)?;
-        for path in dir {
-            let file = path?;
-            if file.file_type()?.is_file()
-                && regex::Regex::new("\\.(png|jpg|jpeg)$")
-                    .unwrap()
-                    .is_match(&file.file_name().to_string_lossy())
-            {
-                let full_file_name = file.file_name().to_string_lossy().to_string();
-                let tex = self
-                    .texture_creator
-                    .load_texture(&PathBuf::from(&dir_path).join(&full_file_name))
-                    .sdl_error()?;
-                let key = full_file_name.split('.').next().unwrap();
-                self.pieces.insert(key.to_string(), tex);
-            }
+        for (key, path) in image_files(&dir_path)? {
+            let tex = self.texture_creator.load_texture(path).sdl_error()?;
+            self.pieces.insert(key, tex);
         }
         Ok(())
     }
 
+    /// The texture a piece on the board is drawn with, found through the
+    /// catalog piece's image
+    fn piece_texture(&self, catalog: &PieceCatalog, piece_name: &str) -> Option<&Texture<'tc>> {
+        let key = match catalog.get_piece(piece_name) {
+            Ok(piece) => texture_key(piece),
+            Err(_) => piece_name,
+        };
+        self.pieces.get(key)
+    }
+
     pub fn render(
         &self,
         canvas: Rc<RwLock<WindowCanvas>>,
         board: &Board,
+        catalog: &PieceCatalog,
     ) -> Result<(), crate::Error> {
         canvas
             .write()
@@ -103,9 +105,28 @@ impl<'tc, C> TextureRegistry<'tc, C> {
                 Some(self.area),
             )
             .sdl_error()?;
+        for space in &board.grid {
+            let color = if space.available_to_kill {
+                Color::RGBA(0xc0, 0x20, 0x20, 0x80)
+            } else if space.available_to_move {
+                Color::RGBA(0x20, 0xc0, 0x20, 0x80)
+            } else {
+                continue;
+            };
+            let mut canvas = canvas.write();
+            canvas.set_draw_color(color);
+            canvas
+                .fill_rect(Rect::new(
+                    board.horz_offset + (space.horz_position * board.space_size) as i32,
+                    board.vert_offset + (space.vert_position * board.space_size) as i32,
+                    board.space_size,
+                    board.space_size,
+                ))
+                .sdl_error()?;
+        }
         let game_pieces = board.collect_game_pieces();
         for game_piece in game_pieces.iter() {
-            let piece_texture = match self.pieces.get(&game_piece.piece_name) {
+            let piece_texture = match self.piece_texture(catalog, &game_piece.piece_name) {
                 Some(pt) => pt,
                 None => continue,
             };
@@ -120,10 +141,53 @@ impl<'tc, C> TextureRegistry<'tc, C> {
                 .copy(piece_texture, None, Some(piece_area))
                 .sdl_error()?;
         }
+        if let Some(pending) = &board.pending_promotion {
+            let mut canvas = canvas.write();
+            canvas.set_draw_color(Color::RGBA(0x00, 0x00, 0x00, 0x80));
+            canvas.fill_rect(self.area).sdl_error()?;
+            for (i, choice) in pending.choices.iter().enumerate() {
+                let choice_area = board.promotion_choice_area(i, pending.choices.len());
+                canvas.set_draw_color(Color::RGB(0x80, 0x80, 0x80));
+                canvas.fill_rect(choice_area).sdl_error()?;
+                if let Some(piece_texture) = self.piece_texture(catalog, choice) {
+                    canvas
+                        .copy(piece_texture, None, Some(choice_area))
+                        .sdl_error()?;
+                }
+            }
+        }
         Ok(())
     }
 }
 
+/// The image files in a directory, each keyed by its name without the extension
+pub fn image_files(dir_path: &str) -> Result<Vec<(String, PathBuf)>, crate::Error> {
+    lazy_static! {
+        static ref IMAGE_FILE: Regex = Regex::new("\\.(png|jpg|jpeg)$").unwrap();
+    }
+    let mut files = vec![];
+    for path in fs::read_dir(dir_path)? {
+        let file = path?;
+        if file.file_type()?.is_file() && IMAGE_FILE.is_match(&file.file_name().to_string_lossy()) {
+            let full_file_name = file.file_name().to_string_lossy().to_string();
+            let key = full_file_name.split('.').next().unwrap().to_string();
+            files.push((key, PathBuf::from(dir_path).join(&full_file_name)));
+        }
+    }
+    Ok(files)
+}
+
+/// The key of the texture a piece is drawn with: its `Image:` file without the
+/// extension, or its name when it has no image
+pub fn texture_key(piece: &Piece) -> &str {
+    let image = if piece.image_key.is_empty() {
+        &piece.name
+    } else {
+        &piece.image_key
+    };
+    image.split('.').next().unwrap_or(image)
+}
+
 #[derive(Debug)]
 pub struct UninitializedTextureRegistryError {}
 
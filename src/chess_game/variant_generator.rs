@@ -0,0 +1,289 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::{fmt::Display, ops::RangeInclusive};
+
+use super::board::Board;
+use super::game_piece::GamePiece;
+use super::piece_catalog::PieceCatalog;
+use super::piece_value;
+use super::player::{Orientation, Player};
+use super::promotion::{PromotionArea, PromotionZone};
+
+/// The ranges a generated variant is drawn from
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantConfig {
+    pub width: RangeInclusive<u32>,
+    pub height: RangeInclusive<u32>,
+    /// How many squares each player's half of the board loses; every one is
+    /// mirrored on the other half
+    pub disabled_squares: RangeInclusive<u32>,
+    /// The points each army spends on pieces besides its royal piece
+    pub budget: u32,
+}
+
+impl Default for VariantConfig {
+    fn default() -> Self {
+        Self {
+            width: 6..=10,
+            height: 6..=10,
+            disabled_squares: 0..=4,
+            budget: 40,
+        }
+    }
+}
+
+/// A piece name and the square it starts on
+type Placement = (String, (u32, u32));
+
+/// Builds two-player boards from a seed, with the same army for both players
+/// mirrored across the middle of the board
+pub struct VariantGenerator<'a> {
+    catalog: &'a PieceCatalog,
+    config: VariantConfig,
+}
+
+impl<'a> VariantGenerator<'a> {
+    pub fn new(catalog: &'a PieceCatalog, config: VariantConfig) -> Self {
+        Self { catalog, config }
+    }
+
+    /// The same seed always gives the same board
+    pub fn generate(&self, seed: u64) -> Result<Board, crate::Error> {
+        let config = &self.config;
+        if config.width.is_empty() || *config.width.start() == 0 {
+            return Err(VariantConfigError::new("the width range needs a width above zero").into());
+        }
+        if config.height.is_empty() || *config.height.start() < 4 {
+            return Err(
+                VariantConfigError::new("the height range needs heights of 4 or more").into(),
+            );
+        }
+        if config.disabled_squares.is_empty() {
+            return Err(VariantConfigError::new("the disabled squares range is empty").into());
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let width = rng.gen_range(config.width.start(), config.width.end() + 1);
+        let height = rng.gen_range(config.height.start(), config.height.end() + 1);
+
+        let mut board = Board::new()?;
+        board.name = format!("Random Variant {}", seed);
+        board.resize(width, height)?;
+        for (name, orientation) in &[("white", Orientation::Up), ("black", Orientation::Down)] {
+            board
+                .players
+                .push(Player::new(name.to_string(), *orientation));
+            board.promotion_zones.push(PromotionZone {
+                team_name: name.to_string(),
+                area: PromotionArea::Ranks(1),
+            });
+        }
+
+        // Holes only go between the armies, so every piece has a square
+        let mut holes = vec![];
+        for vert in 3..=height.div_ceil(2) {
+            for horz in 1..=width {
+                holes.push((horz, vert));
+            }
+        }
+        let hole_count = rng.gen_range(
+            config.disabled_squares.start(),
+            config.disabled_squares.end() + 1,
+        );
+        for &(horz, vert) in holes.choose_multiple(&mut rng, hole_count as usize) {
+            for vert in &[vert, height + 1 - vert] {
+                let index = board.find_board_space_index(horz, *vert);
+                board.grid[index as usize].is_active = false;
+            }
+        }
+
+        for (piece_name, (horz, vert)) in self.army(&mut rng, (width, height))? {
+            board.place_piece(GamePiece::new(
+                piece_name.clone(),
+                "white".to_string(),
+                horz,
+                vert,
+            )?);
+            board.place_piece(GamePiece::new(
+                piece_name,
+                "black".to_string(),
+                horz,
+                height + 1 - vert,
+            )?);
+        }
+        Ok(board)
+    }
+
+    /// White's army and where it stands on the first two ranks: a royal piece
+    /// on the back rank, then random pieces while the budget lasts. Pieces that
+    /// promote go on the second rank when there is room, the rest behind them.
+    /// Pieces are priced for the size of the board they play on.
+    fn army(
+        &self,
+        rng: &mut StdRng,
+        (width, height): (u32, u32),
+    ) -> Result<Vec<Placement>, crate::Error> {
+        let mut pieces = self.catalog.catalog.values().collect::<Vec<_>>();
+        pieces.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut free = [(1..=width).collect::<Vec<_>>(), (1..=width).collect()];
+        let mut army = vec![];
+
+        let royals = pieces
+            .iter()
+            .filter(|piece| piece.royal)
+            .collect::<Vec<_>>();
+        if let Some(royal) = royals.choose(rng) {
+            army.push((royal.name.clone(), free_square(&mut free, 0, rng)));
+        }
+        let mut others = vec![];
+        for piece in pieces.iter().filter(|piece| !piece.royal) {
+            others.push((*piece, piece_value::estimate_value(piece, (width, height))?));
+        }
+        let mut budget = self.config.budget;
+        while free.iter().any(|files| !files.is_empty()) {
+            let affordable = others
+                .iter()
+                .filter(|(_, points)| *points <= budget)
+                .collect::<Vec<_>>();
+            let (piece, points) = match affordable.choose(rng) {
+                Some(choice) => choice,
+                None => break,
+            };
+            budget -= points;
+            let rank = if piece.promotions.is_empty() { 0 } else { 1 };
+            army.push((piece.name.clone(), free_square(&mut free, rank, rng)));
+        }
+        Ok(army)
+    }
+}
+
+/// Takes a random free file on the rank, or on the other rank once it is full
+fn free_square(free: &mut [Vec<u32>; 2], rank: usize, rng: &mut StdRng) -> (u32, u32) {
+    let rank = if free[rank].is_empty() {
+        1 - rank
+    } else {
+        rank
+    };
+    let index = rng.gen_range(0, free[rank].len());
+    (free[rank].swap_remove(index), rank as u32 + 1)
+}
+
+#[derive(Debug)]
+pub struct VariantConfigError {
+    reason: String,
+}
+
+impl VariantConfigError {
+    fn new(reason: &str) -> Self {
+        Self {
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Display for VariantConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cannot generate a variant: {}", self.reason)
+    }
+}
+
+impl std::error::Error for VariantConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_game::test_support::catalog;
+    use expect_test::{expect, Expect};
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    #[test]
+    fn a_seed_always_gives_the_same_variant() {
+        let catalog = catalog();
+        let config = VariantConfig {
+            width: 5..=6,
+            height: 6..=7,
+            disabled_squares: 1..=2,
+            budget: 12,
+        };
+        let generator = VariantGenerator::new(&catalog, config);
+        let board = generator.generate(7).unwrap();
+        check(
+            expect![[r#"
+            ----------------------------
+            Name: Random Variant 7
+            Size: 5 6
+            ----------------------------
+            Player: white up
+            Player: black down
+            ----------------------------
+            PromotionZone: white 1
+            PromotionZone: black 1
+            ----------------------------
+            Disabled: 2 3
+            Disabled: 2 4
+            Disabled: 3 3
+            Disabled: 3 4
+            ----------------------------
+            Piece: 1 1 white Queen
+            Piece: 1 6 black Queen
+            Piece: 2 1 white Queen
+            Piece: 2 6 black Queen
+            Piece: 5 1 white King
+            Piece: 5 6 black King
+            ----------------------------
+        "#]],
+            board.write_board(),
+        );
+        assert_eq!(generator.generate(7).unwrap(), board);
+        assert_ne!(generator.generate(8).unwrap(), board);
+    }
+
+    #[test]
+    fn armies_mirror_each_other_within_the_budget() {
+        let catalog = catalog();
+        let generator = VariantGenerator::new(&catalog, VariantConfig::default());
+        for seed in 0..20 {
+            let board = generator.generate(seed).unwrap();
+            let mut spent = 0;
+            for game_piece in board.collect_game_pieces() {
+                let (horz, vert) = (game_piece.horz_position, game_piece.vert_position);
+                if game_piece.team_name != "white" {
+                    continue;
+                }
+                let mirror = board.get_piece(horz, board.height + 1 - vert).unwrap();
+                assert_eq!(mirror.piece_name, game_piece.piece_name);
+                assert_eq!(mirror.team_name, "black");
+                let piece = catalog.get_piece(&game_piece.piece_name).unwrap();
+                if !piece.royal {
+                    spent +=
+                        piece_value::estimate_value(piece, (board.width, board.height)).unwrap();
+                }
+            }
+            assert!(spent <= 40, "seed {} spent {}", seed, spent);
+            assert_eq!(board.collect_game_pieces().len() % 2, 0);
+
+            let mut read_back = Board::new().unwrap();
+            read_back
+                .read_board(board.write_board().as_bytes(), &catalog)
+                .unwrap();
+            assert_eq!(read_back, board);
+        }
+    }
+
+    #[test]
+    fn rejects_boards_too_small_for_two_armies() {
+        let catalog = catalog();
+        let config = VariantConfig {
+            height: 3..=8,
+            ..VariantConfig::default()
+        };
+        check(
+            expect![[r#"Cannot generate a variant: the height range needs heights of 4 or more"#]],
+            VariantGenerator::new(&catalog, config)
+                .generate(1)
+                .unwrap_err()
+                .to_string(),
+        );
+    }
+}
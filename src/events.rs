@@ -1,9 +1,12 @@
 use std::rc::Rc;
 
 use parking_lot::RwLock;
-use sdl2::{event::Event, mouse::MouseButton, rect::Rect, render::WindowCanvas};
+use sdl2::{
+    event::Event, keyboard::Keycode, mouse::MouseButton, rect::Rect, render::WindowCanvas,
+    ttf::Font,
+};
 
-use crate::{chess_game::ChessGame, gfx::Widgety};
+use crate::{chess_game::ChessGame, gfx::Widgety, sdl_error::ToSdl};
 
 pub struct EventHandler<'tc, C> {
     chess_game: Rc<RwLock<ChessGame<'tc, C>>>,
@@ -35,36 +38,58 @@ impl<'tc, C> EventHandler<'tc, C> {
             Event::RenderTargetsReset { .. } => {
                 self.chess_game.write().render_board(
                     self.canvas.clone(),
-                    self.width as u32,
-                    self.height as u32,
+                    self.width,
+                    self.height,
                 )?;
             }
-            Event::Window { win_event, .. } => {
-                if let sdl2::event::WindowEvent::SizeChanged(w, h) = win_event {
-                    self.width = *w as u32;
-                    self.height = *h as u32;
-                    self.chess_game.write().render_board(
-                        self.canvas.clone(),
-                        self.width as u32,
-                        self.height as u32,
-                    )?;
-                }
+            Event::Window {
+                win_event: sdl2::event::WindowEvent::SizeChanged(w, h),
+                ..
+            } => {
+                self.width = *w as u32;
+                self.height = *h as u32;
+                self.chess_game.write().render_board(
+                    self.canvas.clone(),
+                    self.width,
+                    self.height,
+                )?;
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Tab),
+                ..
+            } => {
+                self.chess_game.write().toggle_board_menu();
+                self.show_title()?;
             }
             Event::MouseMotion { x, y, .. } => {
                 self.chess_game.write().mouse_hover(x, y)?;
+                self.show_title()?;
             }
-            Event::MouseButtonDown { mouse_btn, .. } => {
-                if *mouse_btn == MouseButton::Left {
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                ..
+            } => {
+                let menu_open = self.chess_game.read().board_menu_open();
+                if !menu_open {
                     self.chess_game.write().mouse_left_click()?;
+                    self.show_title()?;
+                } else if self.chess_game.write().pick_hovered_board()? {
+                    self.chess_game.write().render_board(
+                        self.canvas.clone(),
+                        self.width,
+                        self.height,
+                    )?;
+                    self.show_title()?;
                 }
             }
             Event::MouseButtonUp {
-                mouse_btn, x, y, ..
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
             } => {
-                if *mouse_btn == MouseButton::Left {
-                    let rect = Rect::new(0, 0, 0, 0);
-                    if rect.contains_point((*x, *y)) {}
-                }
+                let rect = Rect::new(0, 0, 0, 0);
+                if rect.contains_point((*x, *y)) {}
             }
             _ => {}
         }
@@ -74,9 +99,24 @@ impl<'tc, C> EventHandler<'tc, C> {
         Ok(())
     }
 
-    pub fn draw_widgets(&self) -> Result<(), crate::Error> {
+    /// Puts the board under the mouse in the window title while the board
+    /// picker is open, and otherwise the board and the state of the game
+    fn show_title(&self) -> Result<(), crate::Error> {
+        let title = match self.chess_game.read().hovered_board() {
+            Some(info) => info.to_string(),
+            None => self.chess_game.read().title(),
+        };
+        self.canvas
+            .write()
+            .window_mut()
+            .set_title(&title)
+            .map_err(|e| e.to_string())
+            .sdl_error()
+    }
+
+    pub fn draw_widgets(&self, font: &Font) -> Result<(), crate::Error> {
         for widget in &self.widgets {
-            widget.draw(self.canvas.clone())?;
+            widget.draw(self.canvas.clone(), font)?;
         }
         Ok(())
     }
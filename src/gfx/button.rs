@@ -1,12 +1,13 @@
 use std::rc::Rc;
 
+use super::text;
 use super::Widget;
 use super::Widgety;
 
 use parking_lot::RwLock;
 use sdl2::event::Event;
 use sdl2::pixels::Color;
-use sdl2::{mouse::MouseButton, render::WindowCanvas};
+use sdl2::{mouse::MouseButton, render::WindowCanvas, ttf::Font};
 
 use crate::{sdl_error::ToSdl, Error};
 
@@ -26,12 +27,19 @@ pub struct Button {
 }
 
 impl Widgety for Button {
-    fn draw(&self, canvas: Rc<RwLock<WindowCanvas>>) -> Result<(), Error> {
+    fn draw(&self, canvas: Rc<RwLock<WindowCanvas>>, font: &Font) -> Result<(), Error> {
         let mut canvas = canvas.write();
         canvas.set_draw_color(self.widget.color);
         canvas.fill_rect(self.widget.rect).sdl_error()?;
         canvas.set_draw_color(Color::BLACK);
         canvas.draw_rect(self.widget.rect).sdl_error()?;
+        text::draw_text(
+            &mut canvas,
+            font,
+            &self.text,
+            self.widget.rect,
+            Color::BLACK,
+        )?;
         match self.state {
             State::Normal => {}
             State::Hovered => {
@@ -47,32 +55,32 @@ impl Widgety for Button {
     }
     fn handle_event(&mut self, event: &Event) -> Result<(), Error> {
         match event {
-            Event::MouseMotion { x, y, .. } => {
-                if self.state != State::Pressed {
-                    if self.widget.rect.contains_point((*x, *y)) {
-                        if self.state == State::Normal {
-                            self.state = State::Hovered;
-                        }
-                    } else if self.state == State::Hovered {
-                        self.state = State::Normal;
+            Event::MouseMotion { x, y, .. } if self.state != State::Pressed => {
+                if self.widget.rect.contains_point((*x, *y)) {
+                    if self.state == State::Normal {
+                        self.state = State::Hovered;
                     }
+                } else if self.state == State::Hovered {
+                    self.state = State::Normal;
                 }
             }
-            Event::MouseButtonDown { mouse_btn, .. } => {
-                if self.state == State::Hovered && *mouse_btn == MouseButton::Left {
-                    self.state = State::Pressed;
-                }
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                ..
+            } if self.state == State::Hovered => {
+                self.state = State::Pressed;
             }
             Event::MouseButtonUp {
-                mouse_btn, x, y, ..
-            } => {
-                if self.state == State::Pressed && *mouse_btn == MouseButton::Left {
-                    if self.widget.rect.contains_point((*x, *y)) {
-                        self.state = State::Hovered;
-                        (self.on_click)()?;
-                    } else {
-                        self.state = State::Normal;
-                    }
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } if self.state == State::Pressed => {
+                if self.widget.rect.contains_point((*x, *y)) {
+                    self.state = State::Hovered;
+                    (self.on_click)()?;
+                } else {
+                    self.state = State::Normal;
                 }
             }
             _ => {}
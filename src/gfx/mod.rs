@@ -1,6 +1,8 @@
 pub mod button;
 pub use button::Button;
 
+pub mod text;
+
 pub mod widget;
 pub use widget::Widget;
 pub use widget::Widgety;
@@ -0,0 +1,34 @@
+use sdl2::{pixels::Color, rect::Rect, render::WindowCanvas, ttf::Font};
+
+use crate::{sdl_error::ToSdl, Error};
+
+/// The font every label is drawn in
+pub const FONT_FILE: &str = "./chess_fonts/DejaVuSansMono.ttf";
+/// The size the font is loaded at; labels are scaled from there to fit
+pub const FONT_SIZE: u16 = 32;
+
+/// Draws a line of text centred in `area`, scaled to fit inside it
+pub fn draw_text(
+    canvas: &mut WindowCanvas,
+    font: &Font,
+    text: &str,
+    area: Rect,
+    color: Color,
+) -> Result<(), Error> {
+    if text.is_empty() {
+        return Ok(());
+    }
+    let surface = font.render(text).blended(color).sdl_error()?;
+    let texture_creator = canvas.texture_creator();
+    let texture = texture_creator.create_texture_from_surface(&surface)?;
+    let fit_width = area.width() as f64 * 0.9 / surface.width() as f64;
+    let fit_height = area.height() as f64 * 0.6 / surface.height() as f64;
+    let scale = fit_width.min(fit_height);
+    let width = (surface.width() as f64 * scale) as u32;
+    let height = (surface.height() as f64 * scale) as u32;
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+    let target = Rect::from_center(area.center(), width, height);
+    canvas.copy(&texture, None, target).sdl_error()
+}
@@ -3,7 +3,7 @@ use std::rc::Rc;
 use parking_lot::RwLock;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use sdl2::{event::Event, render::WindowCanvas};
+use sdl2::{event::Event, render::WindowCanvas, ttf::Font};
 
 use crate::Error;
 
@@ -13,12 +13,11 @@ pub struct Widget {
 }
 
 pub trait Widgety {
-    fn draw(&self, canvas: Rc<RwLock<WindowCanvas>>) -> Result<(), Error>;
+    fn draw(&self, canvas: Rc<RwLock<WindowCanvas>>, font: &Font) -> Result<(), Error>;
     fn handle_event(&mut self, event: &Event) -> Result<(), Error>;
 }
 
 impl Widget {
-    #[allow(dead_code)]
     pub fn new(rect: Option<Rect>) -> Widget {
         Widget {
             rect: if let Some(rect) = rect {
@@ -17,8 +17,236 @@ use std::rc::Rc;
 
 use gfx::Button;
 
+const USAGE: &str =
+    "usage: chess-variants-rust [--board <name or file>] [--random <seed>] [--shuffle <seed>] [--shuffle-position <number>] [--shuffle-index <index>] [--list-boards] [--piece-values] [--generate-pieces <count>] [--piece-seed <seed>] [--write-board <file>]";
+/// Where `--generate-pieces` writes its piece files
+const GENERATED_PIECE_DIR: &str = "./generated_pieces/";
+/// The flags that do something else instead of playing
+const ACTIONS: [&str; 4] = [
+    "--list-boards",
+    "--piece-values",
+    "--generate-pieces",
+    "--write-board",
+];
+
+/// What the command line asked for
+#[derive(Default)]
+struct Options {
+    /// A board name from the library, or the path to a board file
+    board: Option<String>,
+    /// Play a generated variant instead of a board file
+    seed: Option<u64>,
+    /// Shuffle the back ranks Chess960-style
+    shuffle: Option<chess_game::shuffle::ShufflePosition>,
+    list_boards: bool,
+    piece_values: bool,
+    /// How many new pieces to invent, starting from the piece seed
+    generate_pieces: Option<u64>,
+    piece_seed: Option<u64>,
+    /// Where to save the board that would be played, instead of playing it
+    write_board: Option<std::path::PathBuf>,
+    /// Every argument given, in order, so flags can be checked against each other
+    flags: Vec<String>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, Error> {
+        let mut options = Options::default();
+        while let Some(arg) = args.next() {
+            options.flags.push(arg.clone());
+            match arg.as_str() {
+                "--board" => {
+                    let board = args
+                        .next()
+                        .ok_or_else(|| Error::Usage(String::from("--board needs a board")))?;
+                    options.board = Some(board);
+                }
+                "--random" => {
+                    let seed = args
+                        .next()
+                        .and_then(|seed| seed.parse().ok())
+                        .ok_or_else(|| Error::Usage(String::from("--random needs a number")))?;
+                    options.seed = Some(seed);
+                }
+                "--shuffle" | "--shuffle-position" | "--shuffle-index" => {
+                    let number = args.next().and_then(|number| number.parse().ok());
+                    let number =
+                        number.ok_or_else(|| Error::Usage(format!("{} needs a number", arg)))?;
+                    options.shuffle = Some(match arg.as_str() {
+                        "--shuffle" => chess_game::shuffle::ShufflePosition::Seed(number),
+                        "--shuffle-position" => {
+                            chess_game::shuffle::ShufflePosition::Chess960(number as usize)
+                        }
+                        _ => chess_game::shuffle::ShufflePosition::Index(number as usize),
+                    });
+                }
+                "--list-boards" => options.list_boards = true,
+                "--piece-values" => options.piece_values = true,
+                "--generate-pieces" => {
+                    let count = args
+                        .next()
+                        .and_then(|count| count.parse().ok())
+                        .ok_or_else(|| {
+                            Error::Usage(String::from("--generate-pieces needs a number"))
+                        })?;
+                    options.generate_pieces = Some(count);
+                }
+                "--piece-seed" => {
+                    let seed = args
+                        .next()
+                        .and_then(|seed| seed.parse().ok())
+                        .ok_or_else(|| Error::Usage(String::from("--piece-seed needs a number")))?;
+                    options.piece_seed = Some(seed);
+                }
+                "--write-board" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| Error::Usage(String::from("--write-board needs a file")))?;
+                    options.write_board = Some(path.into());
+                }
+                _ => return Err(Error::Usage(format!("unknown argument '{}'", arg))),
+            }
+        }
+        options.check()?;
+        Ok(options)
+    }
+
+    /// Refuses flags that would be ignored: a second action, settings the
+    /// action doesn't use, and ways of setting up the board that rule each other out
+    fn check(&self) -> Result<(), Error> {
+        let conflict =
+            |a: &str, b: &str| Err(Error::Usage(format!("{} and {} don't go together", a, b)));
+        for (i, flag) in self.flags.iter().enumerate() {
+            if flag.starts_with("--") && self.flags[..i].contains(flag) {
+                return Err(Error::Usage(format!("{} is given twice", flag)));
+            }
+        }
+        let given = self
+            .flags
+            .iter()
+            .filter(|flag| flag.starts_with("--"))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let actions = given
+            .iter()
+            .filter(|flag| ACTIONS.contains(flag))
+            .collect::<Vec<_>>();
+        if actions.len() > 1 {
+            return conflict(actions[0], actions[1]);
+        }
+        // Playing is the action when no other is given
+        let action = actions.first().map_or("", |action| action);
+        for flag in given.iter().filter(|flag| !ACTIONS.contains(flag)) {
+            let used_by: &[&str] = match *flag {
+                "--board" => &["", "--write-board", "--piece-values"],
+                "--piece-seed" => &["--generate-pieces"],
+                _ => &["", "--write-board"],
+            };
+            if !used_by.contains(&action) {
+                return match action {
+                    "" => Err(Error::Usage(format!("{} needs {}", flag, used_by[0]))),
+                    _ => conflict(flag, action),
+                };
+            }
+        }
+        // A random variant makes its own board, so there is none to pick or shuffle
+        let setups = given
+            .iter()
+            .filter(|flag| {
+                **flag == "--random" || **flag == "--board" || flag.starts_with("--shuffle")
+            })
+            .collect::<Vec<_>>();
+        let shuffles = setups
+            .iter()
+            .filter(|flag| flag.starts_with("--shuffle"))
+            .collect::<Vec<_>>();
+        if shuffles.len() > 1 {
+            return conflict(shuffles[0], shuffles[1]);
+        }
+        if self.seed.is_some() && setups.len() > 1 {
+            let other = setups.iter().find(|flag| ***flag != "--random").unwrap();
+            return conflict("--random", other);
+        }
+        Ok(())
+    }
+}
+
 fn main() {
     let result = (|| -> Result<(), Error> {
+        let options = Options::parse(std::env::args().skip(1))?;
+        if options.list_boards {
+            let library = chess_game::board_library::BoardLibrary::scan(chess_game::BOARD_DIR)?;
+            for info in &library.boards {
+                println!("{}", info);
+            }
+            for problem in &library.problems {
+                eprintln!("Skipped {}", problem);
+            }
+            return Ok(());
+        }
+        if options.piece_values {
+            let mut catalog = chess_game::piece_catalog::PieceCatalog::new()?;
+            catalog.generate("./chess_pieces/".to_string())?;
+            // Pieces are valued on the board asked for, or else on a standard 8x8 one
+            let size = match &options.board {
+                Some(board) => {
+                    let library =
+                        chess_game::board_library::BoardLibrary::scan(chess_game::BOARD_DIR)?;
+                    let info = library.find(board)?;
+                    (info.width, info.height)
+                }
+                None => (8, 8),
+            };
+            print!(
+                "{}",
+                chess_game::piece_value::catalog_report(&catalog, size)?
+            );
+            return Ok(());
+        }
+        if let Some(count) = options.generate_pieces {
+            let generator = chess_game::piece_generator::PieceGenerator::new(Default::default());
+            let first = options.piece_seed.unwrap_or(0);
+            for seed in first..first + count {
+                let generated = generator.generate(seed)?;
+                let path = chess_game::piece_generator::write_piece_file(
+                    std::path::Path::new(GENERATED_PIECE_DIR),
+                    &generated,
+                )?;
+                println!("{} (value {})", path.display(), generated.value);
+            }
+            return Ok(());
+        }
+        if let Some(path) = &options.write_board {
+            let mut catalog = chess_game::piece_catalog::PieceCatalog::new()?;
+            catalog.generate("./chess_pieces/".to_string())?;
+            let board = match options.seed {
+                Some(seed) => chess_game::variant_generator::VariantGenerator::new(
+                    &catalog,
+                    Default::default(),
+                )
+                .generate(seed)?,
+                None => {
+                    let library =
+                        chess_game::board_library::BoardLibrary::scan(chess_game::BOARD_DIR)?;
+                    let info = library.find(
+                        options
+                            .board
+                            .as_deref()
+                            .unwrap_or(chess_game::DEFAULT_BOARD),
+                    )?;
+                    let shuffle = options
+                        .shuffle
+                        .map(chess_game::shuffle::ShuffleConfig::chess960);
+                    let mut board = chess_game::board::Board::new()?;
+                    board.generate(&info.path, &catalog, shuffle.as_ref())?;
+                    board
+                }
+            };
+            std::fs::write(path, board.write_board())?;
+            println!("{}", path.display());
+            return Ok(());
+        }
+
         let sdl = sdl2::init().sdl_error()?;
         let sdl_video = sdl.video().sdl_error()?;
         let window = sdl_video
@@ -40,6 +268,10 @@ fn main() {
         canvas.write().set_blend_mode(BlendMode::Blend);
 
         let texture_creator = canvas.read().texture_creator();
+        let ttf = sdl2::ttf::init().sdl_error()?;
+        let font = ttf
+            .load_font(gfx::text::FONT_FILE, gfx::text::FONT_SIZE)
+            .sdl_error()?;
 
         let mut event_pump = sdl.event_pump().sdl_error()?;
 
@@ -47,7 +279,13 @@ fn main() {
         let height = 600u32;
 
         let chess_game = Rc::new(RwLock::new(chess_game::ChessGame::new(&texture_creator)?));
-        chess_game.write().load()?;
+        chess_game.write().shuffle = options
+            .shuffle
+            .map(chess_game::shuffle::ShuffleConfig::chess960);
+        chess_game.write().load(options.board.as_deref())?;
+        if let Some(seed) = options.seed {
+            chess_game.write().load_random_variant(seed)?;
+        }
         chess_game
             .write()
             .render_board(canvas.clone(), width, height)?;
@@ -81,11 +319,13 @@ fn main() {
 
             canvas.write().set_draw_color(Color::RGB(0x20, 0x20, 0x20));
             canvas.write().clear();
-            chess_game
-                .read()
-                .textures
-                .render(canvas.clone(), &chess_game.read().board)?;
-            event_handler.draw_widgets()?;
+            chess_game.read().textures.render(
+                canvas.clone(),
+                &chess_game.read().board,
+                &chess_game.read().piece_catalog,
+            )?;
+            chess_game.read().render_board_menu(canvas.clone(), &font)?;
+            event_handler.draw_widgets(&font)?;
             canvas.write().present();
         }
 
@@ -123,8 +363,89 @@ pub enum Error {
     #[error(transparent)]
     PieceNotFound(#[from] chess_game::piece_catalog::PieceNotFoundError),
 
+    #[error(transparent)]
+    PieceCatalog(#[from] chess_game::piece_catalog::PieceCatalogError),
+
+    #[error(transparent)]
+    BoardNotFound(#[from] chess_game::board_library::BoardNotFoundError),
+
+    #[error(transparent)]
+    VariantConfig(#[from] chess_game::variant_generator::VariantConfigError),
+
+    #[error(transparent)]
+    PieceGeneration(#[from] chess_game::piece_generator::PieceGenerationError),
+
+    #[error(transparent)]
+    Shuffle(#[from] chess_game::shuffle::ShuffleError),
+
+    #[error("{0}\n{}", USAGE)]
+    Usage(String),
+
+    #[error(transparent)]
+    IllegalMove(#[from] chess_game::board::IllegalMoveError),
+
+    #[error(transparent)]
+    Promotion(#[from] chess_game::promotion::PromotionError),
+
+    #[error(transparent)]
+    Betza(#[from] chess_game::betza::BetzaError),
+
     #[error(transparent)]
     UninitializedTextureRegistry(
         #[from] chess_game::texture_registry::UninitializedTextureRegistryError,
     ),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, Expect};
+
+    fn check(expected: Expect, actual: String) {
+        expected.assert_eq(&actual);
+    }
+
+    #[test]
+    fn refuses_flags_that_would_be_ignored() {
+        let parse = |args: &str| match Options::parse(args.split_whitespace().map(String::from)) {
+            Ok(_) => String::from("ok"),
+            Err(Error::Usage(message)) => message,
+            Err(e) => e.to_string(),
+        };
+        check(
+            expect![[r#"
+                --board classic_chess --shuffle 3: ok
+                --random 4 --write-board out.txt: ok
+                --generate-pieces 5 --piece-seed 9: ok
+                --piece-values --board four_player_chess: ok
+                --random 4 --shuffle 3: --random and --shuffle don't go together
+                --shuffle-position 518 --random 4: --random and --shuffle-position don't go together
+                --random 4 --board classic_chess: --random and --board don't go together
+                --shuffle 1 --shuffle-index 2: --shuffle and --shuffle-index don't go together
+                --generate-pieces 5 --random 42: --random and --generate-pieces don't go together
+                --piece-seed 9: --piece-seed needs --generate-pieces
+                --list-boards --piece-values: --list-boards and --piece-values don't go together
+                --list-boards --board classic_chess: --board and --list-boards don't go together
+                --board a --board b: --board is given twice"#]],
+            [
+                "--board classic_chess --shuffle 3",
+                "--random 4 --write-board out.txt",
+                "--generate-pieces 5 --piece-seed 9",
+                "--piece-values --board four_player_chess",
+                "--random 4 --shuffle 3",
+                "--shuffle-position 518 --random 4",
+                "--random 4 --board classic_chess",
+                "--shuffle 1 --shuffle-index 2",
+                "--generate-pieces 5 --random 42",
+                "--piece-seed 9",
+                "--list-boards --piece-values",
+                "--list-boards --board classic_chess",
+                "--board a --board b",
+            ]
+            .iter()
+            .map(|args| format!("{}: {}", args, parse(args)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        );
+    }
+}
@@ -28,6 +28,18 @@ impl<T> ToSdl<T> for Result<T, sdl2::render::TextureValueError> {
     }
 }
 
+impl<T> ToSdl<T> for Result<T, sdl2::ttf::FontError> {
+    fn sdl_error(self) -> Result<T, Error> {
+        self.map_err(|e| Error::Sdl(e.into()))
+    }
+}
+
+impl<T> ToSdl<T> for Result<T, sdl2::ttf::InitError> {
+    fn sdl_error(self) -> Result<T, Error> {
+        self.map_err(|e| Error::Sdl(e.into()))
+    }
+}
+
 impl<T> ToSdl<T> for Result<T, sdl2::IntegerOrSdlError> {
     fn sdl_error(self) -> Result<T, Error> {
         self.map_err(|e| Error::Sdl(e.into()))
@@ -50,4 +62,10 @@ pub enum SdlError {
 
     #[error(transparent)]
     IntegerOrSdl(#[from] sdl2::IntegerOrSdlError),
+
+    #[error("drawing text: {0}")]
+    Font(#[from] sdl2::ttf::FontError),
+
+    #[error("starting the font library: {0}")]
+    TtfInit(#[from] sdl2::ttf::InitError),
 }